@@ -0,0 +1,170 @@
+use std::str::FromStr;
+
+use crate::errors::{Result, SrpkError::FlagInvalid};
+
+/// Flags that take the argument after them as their value.
+const VALUE_FLAGS: [&str; 20] = [
+    "--threshold",
+    "--shares",
+    "--unlock-ms",
+    "--cost",
+    "--timeout",
+    "--username",
+    "--password-fd",
+    "--password-file",
+    "--new-password-fd",
+    "--new-password-file",
+    "--env",
+    "-i",
+    "-o",
+    "--lifetime",
+    "--socket",
+    "--format",
+    "--on-duplicate",
+    "--map",
+    "--on-conflict",
+    "--dir",
+];
+
+/// Command line arguments, split into positional parameters, `--flags`,
+/// and anything after a bare `--`, which is left as-is for another program.
+pub struct Args {
+    positional: Vec<String>,
+    flags: Vec<(String, Option<String>)>,
+    trailing: Vec<String>,
+}
+
+impl Args {
+    /// Split `args` (not including the program name) into parameters and flags.
+    ///
+    /// Flags in `VALUE_FLAGS` consume the argument after them;
+    /// these are the only flags that may have a single dash.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Self {
+        let mut positional: Vec<String> = Vec::new();
+        let mut flags: Vec<(String, Option<String>)> = Vec::new();
+        let mut trailing: Vec<String> = Vec::new();
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                trailing.extend(args.by_ref());
+                break;
+            }
+            if !arg.starts_with("--") && !VALUE_FLAGS.contains(&arg.as_str()) {
+                positional.push(arg);
+                continue;
+            }
+            match arg.split_once('=') {
+                Some((name, value)) => flags.push((name.to_owned(), Some(value.to_owned()))),
+                None if VALUE_FLAGS.contains(&arg.as_str()) => {
+                    let value: Option<String> = args.next();
+                    flags.push((arg, value));
+                }
+                None => flags.push((arg, None)),
+            }
+        }
+
+        Self {
+            positional,
+            flags,
+            trailing,
+        }
+    }
+
+    /// Get positional parameter `index`.
+    pub fn get(&self, index: usize) -> Option<&String> {
+        self.positional.get(index)
+    }
+
+    /// Whether `name` was passed.
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|(n, _)| n == name)
+    }
+
+    /// Get the value of flag `name`, if it was passed with one.
+    pub fn value(&self, name: &str) -> Option<&String> {
+        self.flags
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .and_then(|(_, v)| v.as_ref())
+    }
+
+    /// Get the value of every `name` passed, in order, for flags that can be repeated.
+    pub fn values(&self, name: &str) -> Vec<&String> {
+        self.flags
+            .iter()
+            .filter(|(n, _)| n == name)
+            .filter_map(|(_, v)| v.as_ref())
+            .collect()
+    }
+
+    /// Get the arguments after a bare `--`.
+    pub fn trailing(&self) -> &[String] {
+        &self.trailing
+    }
+
+    /// Get the value of flag `name` parsed as `T`.
+    ///
+    /// Returns `Err(FlagInvalid)` if the flag was passed without a valid value.
+    pub fn value_parse<T: FromStr>(&self, name: &str) -> Result<Option<T>> {
+        if !self.flag(name) {
+            return Ok(None);
+        }
+        match self.value(name).map(|v| v.parse::<T>()) {
+            Some(Ok(v)) => Ok(Some(v)),
+            _ => Err(FlagInvalid(name.to_owned())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Args {
+        Args::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn test_positional_and_flags() {
+        let args: Args = parse(&["recovery", "split", "--threshold", "3", "--shares=5"]);
+        assert_eq!(args.get(0).unwrap(), "recovery");
+        assert_eq!(args.get(1).unwrap(), "split");
+        assert!(args.get(2).is_none());
+        assert_eq!(args.value_parse::<u8>("--threshold").unwrap(), Some(3));
+        assert_eq!(args.value_parse::<u8>("--shares").unwrap(), Some(5));
+
+        let args: Args = parse(&["inject", "-i", "in.tpl", "-o", "out", "-x"]);
+        assert_eq!(args.value("-i").unwrap(), "in.tpl");
+        assert_eq!(args.value("-o").unwrap(), "out");
+        assert_eq!(args.get(1).unwrap(), "-x");
+    }
+
+    #[test]
+    fn test_value_invalid() {
+        let args: Args = parse(&["--threshold", "three"]);
+        assert!(args.value_parse::<u8>("--threshold").is_err());
+        let args: Args = parse(&["--threshold"]);
+        assert!(args.value_parse::<u8>("--threshold").is_err());
+        assert!(args.value_parse::<u8>("--shares").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_trailing() {
+        let args: Args = parse(&[
+            "run",
+            "--env",
+            "A=a",
+            "--env=B=b",
+            "--",
+            "cmd",
+            "--env",
+            "-x",
+        ]);
+        assert_eq!(args.get(0).unwrap(), "run");
+        assert!(args.get(1).is_none());
+        assert_eq!(args.values("--env"), vec!["A=a", "B=b"]);
+        assert_eq!(args.trailing(), ["cmd", "--env", "-x"]);
+    }
+}
@@ -0,0 +1,248 @@
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+
+/// Hash the first event in a vault's audit log chains from.
+pub const GENESIS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// An operation recorded in a vault's audit log.
+///
+/// Each event holds an HMAC of itself and the HMAC of the event before it,
+/// so changing or removing any event but the last breaks the chain after it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Event {
+    /// When it happened, in seconds since the unix epoch.
+    pub time: u64,
+    pub host: String,
+    pub user: String,
+    /// What was done, such as `get` or `rm`.
+    pub action: String,
+    /// The key it was done to, or `None` for the whole vault.
+    pub key: Option<String>,
+    /// Random ID given to the event when it happened, kept when it is carried to another copy,
+    /// so the same thing done twice in a second is still two events.
+    pub nonce: String,
+    pub hash: String,
+}
+
+impl Event {
+    /// Describe `action` on `key`, done at `time` by this host and user,
+    /// following the event hashed `prev` in a log chained with `chain`.
+    pub fn new(action: &str, key: Option<&str>, time: u64, prev: &str, chain: &[u8; 32]) -> Self {
+        let event: Self = Self {
+            time,
+            host: hostname(),
+            user: username(),
+            action: action.to_owned(),
+            key: key.map(str::to_owned),
+            nonce: crate::crypt::generate_key()[..8]
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect(),
+            hash: String::new(),
+        };
+        event.rechain(prev, chain)
+    }
+
+    /// Copy this event to follow the event hashed `prev` in a log chained with `chain`,
+    /// such as when carrying it over from another copy of the vault.
+    pub fn rechain(&self, prev: &str, chain: &[u8; 32]) -> Self {
+        let mut event: Self = self.clone();
+        event.hash = self.digest(prev, chain);
+        event
+    }
+
+    /// HMAC this event with `chain`, following the event hashed `prev`.
+    fn digest(&self, prev: &str, chain: &[u8; 32]) -> String {
+        let fields = json!([
+            prev,
+            self.time,
+            self.host,
+            self.user,
+            self.action,
+            self.key,
+            self.nonce
+        ]);
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(chain).unwrap();
+        mac.update(fields.to_string().as_bytes());
+        mac.finalize()
+            .into_bytes()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "time": self.time,
+            "host": self.host,
+            "user": self.user,
+            "action": self.action,
+            "key": self.key,
+            "nonce": self.nonce,
+            "hash": self.hash,
+        })
+    }
+
+    pub fn from_json(value: &Value) -> Option<Self> {
+        Some(Self {
+            time: value["time"].as_u64()?,
+            host: value["host"].as_str()?.to_owned(),
+            user: value["user"].as_str()?.to_owned(),
+            action: value["action"].as_str()?.to_owned(),
+            key: match &value["key"] {
+                Value::Null => None,
+                key => Some(key.as_str()?.to_owned()),
+            },
+            nonce: value["nonce"].as_str()?.to_owned(),
+            hash: value["hash"].as_str()?.to_owned(),
+        })
+    }
+
+    /// Whether this and `other` are the same event, whatever chain they are in.
+    pub fn same(&self, other: &Event) -> bool {
+        let unchained = |e: &Event| Event {
+            hash: String::new(),
+            ..e.clone()
+        };
+        unchained(self) == unchained(other)
+    }
+}
+
+/// Derive the key a vault's audit log is chained with from its' data key.
+pub fn chain_key(data_key: &[u8; 32]) -> [u8; 32] {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(data_key).unwrap();
+    mac.update(b"srpk audit log");
+    mac.finalize().into_bytes().into()
+}
+
+/// Check that each of `events` chains from the one before it with `chain`, starting from `GENESIS`,
+/// and that there are `head.0` of them, the last hashed `head.1`.
+///
+/// Returns the index of the first event that does not, or `None` if the log is intact.
+/// A log missing its' last events returns the index of the first missing one.
+pub fn verify(events: &[Event], chain: &[u8; 32], head: Option<(usize, &str)>) -> Option<usize> {
+    let mut prev: &str = GENESIS;
+    for (i, event) in events.iter().enumerate() {
+        if event.digest(prev, chain) != event.hash {
+            return Some(i);
+        }
+        prev = &event.hash;
+    }
+    let (count, hash): (usize, &str) = head.unwrap_or((0, GENESIS));
+    if events.len() != count || prev != hash {
+        return Some(events.len().min(count));
+    }
+    None
+}
+
+/// Get the name of this machine.
+pub fn hostname() -> String {
+    #[cfg(unix)]
+    {
+        let mut name: [u8; 256] = [0u8; 256];
+        if unsafe { libc::gethostname(name.as_mut_ptr() as *mut libc::c_char, name.len()) } == 0 {
+            let end: usize = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+            return String::from_utf8_lossy(&name[..end]).into_owned();
+        }
+    }
+    std::env::var("COMPUTERNAME").unwrap_or_else(|_| "unknown".to_owned())
+}
+
+/// Get the name of the user running srpk.
+pub fn username() -> String {
+    #[cfg(unix)]
+    {
+        let passwd = unsafe { libc::getpwuid(libc::getuid()) };
+        if !passwd.is_null() {
+            let name = unsafe { std::ffi::CStr::from_ptr((*passwd).pw_name) };
+            return name.to_string_lossy().into_owned();
+        }
+    }
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_owned())
+}
+
+/// Format `time`, in seconds since the unix epoch, as a UTC date and time such as `2024-01-31 09:05:00`.
+pub fn timestamp(time: u64) -> String {
+    let (days, secs) = (time / 86400, time % 86400);
+    // civil date from days since the epoch, after Howard Hinnant's days_from_civil
+    let z: u64 = days + 719468;
+    let era: u64 = z / 146097;
+    let doe: u64 = z - era * 146097;
+    let yoe: u64 = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy: u64 = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp: u64 = (5 * doy + 2) / 153;
+    let day: u64 = doy - (153 * mp + 2) / 5 + 1;
+    let month: u64 = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year: u64 = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify() {
+        let chain: [u8; 32] = chain_key(&[7u8; 32]);
+        let first: Event = Event::new("mk", Some("github"), 100, GENESIS, &chain);
+        let second: Event = Event::new("export", None, 200, &first.hash, &chain);
+        let third: Event = Event::new("get", Some("github"), 300, &second.hash, &chain);
+        let mut events: Vec<Event> = vec![first, second, third];
+        let head: String = events[2].hash.clone();
+        assert_eq!(verify(&events, &chain, Some((3, &head))), None);
+        assert_eq!(verify(&[], &chain, None), None);
+
+        // without the key the chain cannot be rebuilt
+        assert_eq!(
+            verify(&events, &chain_key(&[8u8; 32]), Some((3, &head))),
+            Some(0)
+        );
+
+        // dropping the newest events is caught by the head
+        assert_eq!(verify(&events[..2], &chain, Some((3, &head))), Some(2));
+        assert_eq!(verify(&events, &chain, None), Some(0));
+
+        events[1].key = Some("mail".to_owned());
+        assert_eq!(verify(&events, &chain, Some((3, &head))), Some(1));
+        events.remove(1);
+        assert_eq!(verify(&events, &chain, Some((3, &head))), Some(1));
+        events[0].time = 99;
+        assert_eq!(verify(&events, &chain, Some((3, &head))), Some(0));
+    }
+
+    #[test]
+    fn test_rechain() {
+        let ours: [u8; 32] = chain_key(&[1u8; 32]);
+        let theirs: [u8; 32] = chain_key(&[2u8; 32]);
+        let event: Event = Event::new("rm", Some("github"), 100, GENESIS, &theirs);
+        let copied: Event = event.rechain(GENESIS, &ours);
+        assert!(copied.same(&event));
+        assert_ne!(copied.hash, event.hash);
+        assert_eq!(
+            verify(
+                std::slice::from_ref(&copied),
+                &ours,
+                Some((1, &copied.hash))
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(timestamp(0), "1970-01-01 00:00:00");
+        assert_eq!(timestamp(951782400 + 3723), "2000-02-29 01:02:03");
+        assert_eq!(timestamp(1706691900), "2024-01-31 09:05:00");
+    }
+}
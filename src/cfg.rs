@@ -2,25 +2,48 @@ use std::{
     env::current_dir,
     fs::{read, write},
     path::{Path, PathBuf},
+    str::FromStr,
 };
 
 use crate::errors::{
     Result,
-    SrpkError::{ConfigDir, PathEmpty},
+    SrpkError::{ConfigDir, PathEmpty, SettingInvalid, SettingUnknown},
 };
+use crate::oplog;
 
-fn cfg_path() -> Result<PathBuf> {
+/// Settings that can be changed with `srpk config`.
+pub const SETTINGS: [&str; 5] = [
+    "audit_reads",
+    "clipboard_timeout",
+    "clipboard_backend",
+    "sync_key_names",
+    "trash_retention_days",
+];
+
+fn cfg_file(name: &str) -> Result<PathBuf> {
     if let Some(mut config_home) = dirs::config_dir() {
-        config_home.push(".srpkvault");
+        config_home.push(name);
         return Ok(config_home);
     }
     if let Some(mut user_home) = dirs::home_dir() {
-        user_home.push(".srpkvault");
+        user_home.push(name);
         return Ok(user_home);
     }
     Err(ConfigDir)
 }
 
+fn cfg_path() -> Result<PathBuf> {
+    cfg_file(".srpkvault")
+}
+
+fn settings_path() -> Result<PathBuf> {
+    cfg_file(".srpkconfig")
+}
+
+fn device_path() -> Result<PathBuf> {
+    cfg_file(".srpkdevice")
+}
+
 pub fn get_active_vault() -> Result<Option<PathBuf>> {
     let path: PathBuf = cfg_path()?;
     if !path.exists() {
@@ -54,3 +77,105 @@ pub fn set_active_vault(vault: &Path) -> Result<()> {
     }
     Ok(())
 }
+
+/// Get the ID this device records its' changes to vaults under, making one up the first time.
+pub fn get_device_id() -> Result<String> {
+    let path: PathBuf = device_path()?;
+    if path.exists() {
+        let id: String = String::from_utf8(read(&path)?)?.trim().to_owned();
+        if !id.is_empty() {
+            return Ok(id);
+        }
+    }
+    let id: String = oplog::device_id();
+    write(path, &id)?;
+    Ok(id)
+}
+
+/// Read `name = value` lines, skipping blank lines and `#` comments.
+fn parse_settings(file: &str) -> Vec<(String, String)> {
+    file.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(|l| l.split_once('='))
+        .map(|(name, value)| (name.trim().to_owned(), value.trim().to_owned()))
+        .collect()
+}
+
+fn render_settings(settings: &[(String, String)]) -> String {
+    settings
+        .iter()
+        .map(|(name, value)| format!("{} = {}\n", name, value))
+        .collect()
+}
+
+/// Get every setting that has been changed from its' default.
+pub fn get_settings() -> Result<Vec<(String, String)>> {
+    let path: PathBuf = settings_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let file: String = String::from_utf8(read(&path)?)?;
+    Ok(parse_settings(&file))
+}
+
+/// Get setting `name`, or `None` if it is unset.
+///
+/// Returns `Err(SettingUnknown)` if `name` is not in `SETTINGS`.
+pub fn get_setting(name: &str) -> Result<Option<String>> {
+    if !SETTINGS.contains(&name) {
+        return Err(SettingUnknown(name.to_owned()));
+    }
+    Ok(get_settings()?
+        .into_iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, v)| v))
+}
+
+/// Get setting `name` parsed as `T`, or `None` if it is unset.
+///
+/// Returns `Err(SettingInvalid)` if the value cannot be parsed.
+pub fn get_setting_parse<T: FromStr>(name: &str) -> Result<Option<T>> {
+    match get_setting(name)? {
+        Some(value) => match value.parse::<T>() {
+            Ok(v) => Ok(Some(v)),
+            Err(_) => Err(SettingInvalid(name.to_owned(), value)),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Set setting `name` to `value`, or back to its' default if `value` is `None`.
+///
+/// Returns `Err(SettingUnknown)` if `name` is not in `SETTINGS`.
+pub fn set_setting(name: &str, value: Option<&str>) -> Result<()> {
+    if !SETTINGS.contains(&name) {
+        return Err(SettingUnknown(name.to_owned()));
+    }
+    let mut settings: Vec<(String, String)> = get_settings()?;
+    settings.retain(|(n, _)| n != name);
+    if let Some(value) = value {
+        settings.push((name.to_owned(), value.to_owned()));
+    }
+    write(settings_path()?, render_settings(&settings))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_roundtrip() {
+        let settings: Vec<(String, String)> =
+            parse_settings("# srpk settings\n\nclipboard_timeout = 30\nbroken line\nother=a = b\n");
+        assert_eq!(
+            settings,
+            vec![
+                ("clipboard_timeout".to_owned(), "30".to_owned()),
+                ("other".to_owned(), "a = b".to_owned())
+            ]
+        );
+        assert_eq!(parse_settings(&render_settings(&settings)), settings);
+    }
+}
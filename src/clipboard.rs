@@ -0,0 +1,390 @@
+use arboard::Clipboard;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::{
+    env::current_exe,
+    fmt,
+    fs::{File, OpenOptions},
+    io::{stdin, stdout, BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    str::FromStr,
+    thread::sleep,
+    time::Duration,
+};
+
+use crate::errors::{
+    Result,
+    SrpkError::{ClipboardHelper, NoClipboard, SettingInvalid},
+};
+
+/// Seconds a secret stays in the clipboard if neither `--timeout` nor `clipboard_timeout` is set.
+pub const DEFAULT_TIMEOUT: u64 = 10;
+
+/// Hidden subcommand that runs `helper` in a detached process.
+pub const HELPER: &str = "__clipboard";
+
+/// Somewhere a secret can be put for pasting.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Backend {
+    /// The desktop clipboard.
+    System,
+    /// The X11/Wayland primary selection, pasted with middle click.
+    Primary,
+    /// The clipboard of the terminal, set with an OSC 52 escape sequence; works over SSH.
+    Osc52,
+}
+
+/// Backends tried, in order, if `clipboard_backend` is unset or `auto`.
+const AUTO: [Backend; 2] = [Backend::System, Backend::Osc52];
+
+impl FromStr for Backend {
+    type Err = ();
+
+    fn from_str(s: &str) -> core::result::Result<Self, ()> {
+        match s {
+            "system" => Ok(Backend::System),
+            "primary" => Ok(Backend::Primary),
+            "osc52" => Ok(Backend::Osc52),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Backend::System => "system",
+            Backend::Primary => "primary",
+            Backend::Osc52 => "osc52",
+        })
+    }
+}
+
+/// Read the `clipboard_backend` setting: `auto`, or a comma-separated list of backends to try in order.
+pub fn parse_backends(setting: Option<&str>) -> Result<Vec<Backend>> {
+    match setting {
+        None | Some("auto") => Ok(AUTO.to_vec()),
+        Some(list) => list
+            .split(',')
+            .map(|b| b.trim().parse::<Backend>())
+            .collect::<core::result::Result<Vec<Backend>, ()>>()
+            .map_err(|_| SettingInvalid("clipboard_backend".to_owned(), list.to_owned())),
+    }
+}
+
+/// Find the terminal srpk is running in, for OSC 52.
+#[cfg(unix)]
+pub fn tty_path() -> Option<PathBuf> {
+    for fd in [0, 1, 2] {
+        unsafe {
+            if libc::isatty(fd) == 1 {
+                let name = libc::ttyname(fd);
+                if !name.is_null() {
+                    let name = std::ffi::CStr::from_ptr(name);
+                    return Some(PathBuf::from(name.to_string_lossy().into_owned()));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Find the terminal srpk is running in, for OSC 52.
+#[cfg(not(unix))]
+pub fn tty_path() -> Option<PathBuf> {
+    Some(PathBuf::from("CONOUT$"))
+}
+
+/// A backend while it holds a secret, along with what to put back afterwards.
+enum Held {
+    Arboard {
+        clipboard: Clipboard,
+        backend: Backend,
+        previous: Option<String>,
+    },
+    Osc52 {
+        tty: File,
+    },
+}
+
+#[cfg(all(
+    unix,
+    not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+))]
+mod selection {
+    use arboard::{ClearExtLinux, Clipboard, Error, GetExtLinux, LinuxClipboardKind, SetExtLinux};
+
+    use super::Backend;
+
+    fn kind(backend: Backend) -> LinuxClipboardKind {
+        match backend {
+            Backend::Primary => LinuxClipboardKind::Primary,
+            _ => LinuxClipboardKind::Clipboard,
+        }
+    }
+
+    pub fn get(clipboard: &mut Clipboard, backend: Backend) -> Result<String, Error> {
+        clipboard.get().clipboard(kind(backend)).text()
+    }
+
+    pub fn set(clipboard: &mut Clipboard, backend: Backend, text: &str) -> Result<(), Error> {
+        clipboard.set().clipboard(kind(backend)).text(text)
+    }
+
+    pub fn clear(clipboard: &mut Clipboard, backend: Backend) -> Result<(), Error> {
+        clipboard.clear_with().clipboard(kind(backend))
+    }
+}
+
+#[cfg(not(all(
+    unix,
+    not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+)))]
+mod selection {
+    use arboard::{Clipboard, Error};
+
+    use super::Backend;
+
+    fn check(backend: Backend) -> Result<(), Error> {
+        match backend {
+            Backend::Primary => Err(Error::ClipboardNotSupported),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn get(clipboard: &mut Clipboard, backend: Backend) -> Result<String, Error> {
+        check(backend)?;
+        clipboard.get_text()
+    }
+
+    pub fn set(clipboard: &mut Clipboard, backend: Backend, text: &str) -> Result<(), Error> {
+        check(backend)?;
+        clipboard.set_text(text)
+    }
+
+    pub fn clear(clipboard: &mut Clipboard, backend: Backend) -> Result<(), Error> {
+        check(backend)?;
+        clipboard.clear()
+    }
+}
+
+fn osc52(tty: &mut File, text: &str) -> Result<()> {
+    write!(tty, "\x1b]52;c;{}\x07", STANDARD.encode(text))?;
+    Ok(tty.flush()?)
+}
+
+fn take_with(backend: Backend, secret: &str, tty: Option<&Path>) -> Result<Held> {
+    let unavailable = |e: &dyn fmt::Display| NoClipboard(format!("{}: {}", backend, e));
+    match backend {
+        Backend::Osc52 => {
+            let path: &Path = tty.ok_or_else(|| unavailable(&"not in a terminal"))?;
+            let mut tty: File = OpenOptions::new()
+                .write(true)
+                .open(path)
+                .map_err(|e| unavailable(&e))?;
+            osc52(&mut tty, secret)?;
+            Ok(Held::Osc52 { tty })
+        }
+        _ => {
+            let mut clipboard: Clipboard = Clipboard::new().map_err(|e| unavailable(&e))?;
+            let previous: Option<String> = selection::get(&mut clipboard, backend).ok();
+            selection::set(&mut clipboard, backend, secret).map_err(|e| unavailable(&e))?;
+            Ok(Held::Arboard {
+                clipboard,
+                backend,
+                previous,
+            })
+        }
+    }
+}
+
+/// Put `secret` into the first of `backends` that works.
+///
+/// Returns `Err(NoClipboard)` listing why each backend failed if none do.
+fn take(backends: &[Backend], secret: &str, tty: Option<&Path>) -> Result<(Held, Backend)> {
+    let mut problems: Vec<String> = Vec::new();
+    for backend in backends {
+        match take_with(*backend, secret, tty) {
+            Ok(held) => return Ok((held, *backend)),
+            Err(NoClipboard(problem)) => problems.push(problem),
+            Err(e) => return Err(e),
+        }
+    }
+    Err(NoClipboard(problems.join("; ")))
+}
+
+/// Put back whatever was in the backend before `secret`.
+///
+/// Returns `false` without touching the clipboard if it no longer holds `secret`.
+/// OSC 52 cannot be read back, so the terminal's clipboard is always emptied.
+fn release(held: Held, secret: &str) -> Result<bool> {
+    let (mut clipboard, backend, previous) = match held {
+        Held::Osc52 { mut tty } => {
+            osc52(&mut tty, "")?;
+            return Ok(true);
+        }
+        Held::Arboard {
+            clipboard,
+            backend,
+            previous,
+        } => (clipboard, backend, previous),
+    };
+
+    let unavailable = |e: arboard::Error| NoClipboard(format!("{}: {}", backend, e));
+    match selection::get(&mut clipboard, backend) {
+        Ok(current) if current == secret => {
+            match previous {
+                Some(previous) if previous != secret => {
+                    selection::set(&mut clipboard, backend, &previous).map_err(unavailable)?
+                }
+                _ => selection::clear(&mut clipboard, backend).map_err(unavailable)?,
+            }
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Put `secret` into the first of `backends` that works, and take it back out after `timeout` seconds.
+///
+/// Whatever was in the clipboard beforehand is put back afterwards.
+/// If the clipboard no longer holds `secret` by then, it is left alone.
+///
+/// If `wait` is set, this blocks and shows a countdown.
+/// Otherwise a detached copy of srpk holds the clipboard and clears it,
+/// so this returns immediately and the clear still happens if the terminal is closed.
+pub fn copy(secret: &str, timeout: u64, backends: &[Backend], wait: bool) -> Result<()> {
+    let tty: Option<PathBuf> = tty_path();
+    if !wait {
+        let backend: Backend = spawn_helper(secret, timeout, backends, tty.as_deref())?;
+        println!(
+            "pass has been put into {} clipboard, and will be cleared in {}s",
+            backend, timeout
+        );
+        return Ok(());
+    }
+
+    let (held, backend) = take(backends, secret, tty.as_deref())?;
+    for remaining in (1..=timeout).rev() {
+        print!(
+            "\rpass is in {} clipboard, clearing in {}s ",
+            backend, remaining
+        );
+        stdout().flush()?;
+        sleep(Duration::from_secs(1));
+    }
+    println!();
+
+    match release(held, secret)? {
+        true => println!("clipboard cleared"),
+        false => println!("clipboard has changed since, leaving it alone"),
+    }
+    Ok(())
+}
+
+/// Start a detached `srpk __clipboard <timeout> <backends> [<tty>]`
+/// and hand it `secret` over a pipe, keeping it out of the process list.
+///
+/// Returns the backend the helper put the secret into.
+fn spawn_helper(
+    secret: &str,
+    timeout: u64,
+    backends: &[Backend],
+    tty: Option<&Path>,
+) -> Result<Backend> {
+    let backends: Vec<String> = backends.iter().map(|b| b.to_string()).collect();
+    let mut command: Command = Command::new(current_exe()?);
+    command
+        .arg(HELPER)
+        .arg(timeout.to_string())
+        .arg(backends.join(","))
+        .args(tty)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // leave the terminal's session so closing it does not kill the helper
+        unsafe {
+            command.pre_exec(|| {
+                libc::setsid();
+                Ok(())
+            });
+        }
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const DETACHED_PROCESS: u32 = 0x00000008;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x00000200;
+        command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+    }
+
+    let mut child: Child = command.spawn()?;
+    child.stdin.take().unwrap().write_all(secret.as_bytes())?;
+
+    // the helper reports back once the secret is in the clipboard
+    let mut status: String = String::new();
+    BufReader::new(child.stdout.take().unwrap()).read_line(&mut status)?;
+    match status.trim().split_once(' ') {
+        Some(("ok", backend)) => backend
+            .parse::<Backend>()
+            .map_err(|_| ClipboardHelper(status.trim().to_owned())),
+        Some(("error", e)) => Err(NoClipboard(e.to_owned())),
+        _ => Err(ClipboardHelper("exited early".to_owned())),
+    }
+}
+
+/// Run as the detached helper: read the secret from stdin,
+/// report `ok <backend>` (or `error <reason>`) on stdout,
+/// then clear the clipboard after `timeout` seconds.
+pub fn helper(timeout: u64, backends: &[Backend], tty: Option<&Path>) -> Result<()> {
+    let mut secret: String = String::new();
+    stdin().read_to_string(&mut secret)?;
+
+    let held: Held = match take(backends, &secret, tty) {
+        Ok((held, backend)) => {
+            println!("ok {}", backend);
+            held
+        }
+        Err(NoClipboard(problem)) => {
+            // srpk reports this, nobody is reading our errors
+            println!("error {}", problem);
+            return Ok(());
+        }
+        Err(e) => {
+            println!("error {}", e);
+            return Ok(());
+        }
+    };
+    stdout().flush()?;
+
+    sleep(Duration::from_secs(timeout));
+    release(held, &secret)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_backends() {
+        assert_eq!(parse_backends(None).unwrap(), AUTO.to_vec());
+        assert_eq!(parse_backends(Some("auto")).unwrap(), AUTO.to_vec());
+        assert_eq!(
+            parse_backends(Some("primary, osc52")).unwrap(),
+            vec![Backend::Primary, Backend::Osc52]
+        );
+        assert!(parse_backends(Some("system,x11")).is_err());
+    }
+
+    #[test]
+    fn test_osc52_unavailable() {
+        assert!(matches!(
+            take(&[Backend::Osc52], "secret", None),
+            Err(NoClipboard(_))
+        ));
+    }
+}
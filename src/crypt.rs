@@ -1,18 +1,20 @@
 use aes::cipher::{generic_array::GenericArray, typenum::U32};
 use aes_gcm_siv::aead::rand_core::RngCore;
 use aes_gcm_siv::{
-    aead::{Aead, KeyInit, OsRng},
+    aead::{Aead, KeyInit, OsRng, Payload},
     Aes256GcmSiv, Nonce,
 };
 use bcrypt::HashParts;
 use sha2::{Digest, Sha256};
+use std::time::{Duration, Instant};
+use x25519_dalek::{PublicKey, StaticSecret};
 
 use crate::errors::Result;
 
-struct AES256Key {
-    pub cipher: Aes256GcmSiv,
-    pub salt: [u8; 16],
-}
+/// Lowest bcrypt cost accepted.
+pub const MIN_COST: u8 = 5;
+/// Highest bcrypt cost accepted.
+pub const MAX_COST: u8 = 31;
 
 pub struct CryptValue {
     pub value: Vec<u8>,
@@ -31,39 +33,151 @@ fn generate_salt() -> [u8; 16] {
     salt
 }
 
-fn get_aes256gcmsiv(pass: &str, cost: u8) -> Result<AES256Key> {
-    let salt: [u8; 16] = generate_salt();
-    get_aes256gcmsiv_with_salt(pass, salt, cost)
+/// Generate a random 256-bit key.
+pub fn generate_key() -> [u8; 32] {
+    let mut key: [u8; 32] = [0u8; 32];
+    OsRng.fill_bytes(&mut key);
+    key
 }
 
-fn get_aes256gcmsiv_with_salt(pass: &str, salt: [u8; 16], cost: u8) -> Result<AES256Key> {
+fn derive_key(pass: &str, salt: [u8; 16], cost: u8) -> Result<[u8; 32]> {
     let bcrypt: HashParts = bcrypt::hash_with_salt(pass, cost as u32, salt)?;
     let mut hasher = Sha256::new();
     hasher.update(bcrypt.to_string());
     let hash: GenericArray<u8, U32> = hasher.finalize();
-    let cipher: Aes256GcmSiv = Aes256GcmSiv::new(&hash);
-    Ok(AES256Key { cipher, salt })
+    Ok(hash.into())
+}
+
+/// Measure how long deriving a key at bcrypt `cost` takes on this machine.
+pub fn bcrypt_time(cost: u8) -> Result<Duration> {
+    let start: Instant = Instant::now();
+    derive_key("srpk-bench", generate_salt(), cost)?;
+    Ok(start.elapsed())
+}
+
+/// Find the highest bcrypt cost that should unlock a vault within `target` on this machine.
+///
+/// Each step of cost doubles the time taken, so costs are measured upwards from `MIN_COST`
+/// until the next step is expected to exceed `target`.
+/// Returns `MIN_COST` if even that is slower than `target`.
+pub fn bcrypt_calibrate(target: Duration) -> Result<u8> {
+    let mut cost: u8 = MIN_COST;
+    while cost < MAX_COST && bcrypt_time(cost)? * 2 <= target {
+        cost += 1;
+    }
+    Ok(cost)
+}
+
+/// Turn a `Vec<u8>` into its' encrypted form using a raw `key`.
+///
+/// The output is the nonce followed by the ciphertext.
+pub fn aes256_seal(plaintext: &[u8], key: &[u8; 32]) -> Result<Vec<u8>> {
+    aes256_seal_aad(plaintext, key, &[])
+}
+
+/// Like `aes256_seal`, but opening the output also checks that `aad` is unchanged.
+pub fn aes256_seal_aad(plaintext: &[u8], key: &[u8; 32], aad: &[u8]) -> Result<Vec<u8>> {
+    let nonce_u8: [u8; 12] = generate_nonce();
+    let nonce: &Nonce = &Nonce::from(nonce_u8);
+    let cipher: Aes256GcmSiv = Aes256GcmSiv::new(key.into());
+    let ciphertext: Vec<u8> = cipher.encrypt(
+        nonce,
+        Payload {
+            msg: plaintext,
+            aad,
+        },
+    )?;
+    Ok([nonce_u8.to_vec(), ciphertext].concat())
+}
+
+/// Returns the original bytes of an `aes256_seal` output.
+pub fn aes256_open(bytes: &[u8], key: &[u8; 32]) -> Result<Vec<u8>> {
+    aes256_open_aad(bytes, key, &[])
+}
+
+/// Returns the original bytes of an `aes256_seal_aad` output sealed with `aad`.
+pub fn aes256_open_aad(bytes: &[u8], key: &[u8; 32], aad: &[u8]) -> Result<Vec<u8>> {
+    if bytes.len() < 12 {
+        return Err(aes_gcm_siv::Error.into());
+    }
+    let nonce_u8: [u8; 12] = bytes[..12].try_into().unwrap();
+    let nonce: &Nonce = &Nonce::from(nonce_u8);
+    let cipher: Aes256GcmSiv = Aes256GcmSiv::new(key.into());
+    Ok(cipher.decrypt(
+        nonce,
+        Payload {
+            msg: &bytes[12..],
+            aad,
+        },
+    )?)
 }
 
 /// Returns the original bytes.
 pub fn aes256_decrypt(bytes: &[u8], pass: &str) -> Result<CryptValue> {
+    if bytes.len() < 29 {
+        return Err(aes_gcm_siv::Error.into());
+    }
     let cost: u8 = bytes[0];
     let salt_u8: [u8; 16] = bytes[1..17].try_into().unwrap();
-    let nonce_u8: [u8; 12] = bytes[17..29].try_into().unwrap();
-    let ciphertext_u8: &[u8] = &bytes[29..];
-    let nonce: &Nonce = &Nonce::from(nonce_u8);
-    let key: AES256Key = get_aes256gcmsiv_with_salt(pass, salt_u8, cost)?;
-    let value = key.cipher.decrypt(nonce, ciphertext_u8.as_ref())?;
+    let key: [u8; 32] = derive_key(pass, salt_u8, cost)?;
+    let value: Vec<u8> = aes256_open(&bytes[17..], &key)?;
     Ok(CryptValue { value, cost })
 }
 
 /// Turn a `Vec<u8>` into its' encrypted form using `pass`.
-pub fn aes256_encrypt(plaintext: &Vec<u8>, pass: &str, cost: u8) -> Result<Vec<u8>> {
-    let nonce_u8: [u8; 12] = generate_nonce();
-    let nonce: &Nonce = &Nonce::from(nonce_u8);
-    let key: AES256Key = get_aes256gcmsiv(pass, cost)?;
-    let ciphertext: Vec<u8> = key.cipher.encrypt(nonce, plaintext.as_ref())?;
-    Ok([vec![cost], key.salt.to_vec(), nonce_u8.to_vec(), ciphertext].concat())
+pub fn aes256_encrypt(plaintext: &[u8], pass: &str, cost: u8) -> Result<Vec<u8>> {
+    let salt: [u8; 16] = generate_salt();
+    let key: [u8; 32] = derive_key(pass, salt, cost)?;
+    let sealed: Vec<u8> = aes256_seal(plaintext, &key)?;
+    Ok([vec![cost], salt.to_vec(), sealed].concat())
+}
+
+/// Generate a new X25519 keypair, returned as `(secret, public)`.
+pub fn x25519_generate() -> ([u8; 32], [u8; 32]) {
+    let secret: StaticSecret = StaticSecret::random_from_rng(OsRng);
+    let public: PublicKey = PublicKey::from(&secret);
+    (secret.to_bytes(), public.to_bytes())
+}
+
+/// Get the public key belonging to X25519 `secret`.
+pub fn x25519_public(secret: &[u8; 32]) -> [u8; 32] {
+    PublicKey::from(&StaticSecret::from(*secret)).to_bytes()
+}
+
+fn x25519_wrap_key(shared: &[u8; 32], ephemeral: &[u8; 32], recipient: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"srpk-x25519");
+    hasher.update(shared);
+    hasher.update(ephemeral);
+    hasher.update(recipient);
+    hasher.finalize().into()
+}
+
+/// Encrypt `plaintext` so that only the holder of the secret for `recipient` can read it.
+///
+/// The output is an ephemeral public key followed by an `aes256_seal` payload.
+pub fn x25519_encrypt(plaintext: &[u8], recipient: &[u8; 32]) -> Result<Vec<u8>> {
+    let (ephemeral_secret, ephemeral) = x25519_generate();
+    let shared: [u8; 32] = StaticSecret::from(ephemeral_secret)
+        .diffie_hellman(&PublicKey::from(*recipient))
+        .to_bytes();
+    let key: [u8; 32] = x25519_wrap_key(&shared, &ephemeral, recipient);
+    let sealed: Vec<u8> = aes256_seal(plaintext, &key)?;
+    Ok([ephemeral.to_vec(), sealed].concat())
+}
+
+/// Returns the original bytes of an `x25519_encrypt` output.
+pub fn x25519_decrypt(bytes: &[u8], secret: &[u8; 32]) -> Result<Vec<u8>> {
+    if bytes.len() < 32 {
+        return Err(aes_gcm_siv::Error.into());
+    }
+    let ephemeral: [u8; 32] = bytes[..32].try_into().unwrap();
+    let recipient: [u8; 32] = x25519_public(secret);
+    let shared: [u8; 32] = StaticSecret::from(*secret)
+        .diffie_hellman(&PublicKey::from(ephemeral))
+        .to_bytes();
+    let key: [u8; 32] = x25519_wrap_key(&shared, &ephemeral, &recipient);
+    aes256_open(&bytes[32..], &key)
 }
 
 #[cfg(test)]
@@ -77,7 +191,7 @@ mod tests {
 
     #[test]
     fn test_encrypt_decrypt() {
-        let plaintext_bytes: Vec<u8> = PLAINTEXT.try_into().unwrap();
+        let plaintext_bytes: Vec<u8> = PLAINTEXT.into();
         let encrypted_bytes: Vec<u8> = aes256_encrypt(&plaintext_bytes, PASS, COST).unwrap();
         let decrypted_bytes: CryptValue = aes256_decrypt(&encrypted_bytes, PASS).unwrap();
         assert_eq!(decrypted_bytes.value, plaintext_bytes);
@@ -85,8 +199,30 @@ mod tests {
 
     #[test]
     fn test_decrypt_bad_pass() {
-        let plaintext_bytes: Vec<u8> = PLAINTEXT.try_into().unwrap();
+        let plaintext_bytes: Vec<u8> = PLAINTEXT.into();
         let encrypted_bytes: Vec<u8> = aes256_encrypt(&plaintext_bytes, PASS, COST).unwrap();
         assert!(aes256_decrypt(&encrypted_bytes, BAD_PASS).is_err());
     }
+
+    #[test]
+    fn test_calibrate() {
+        assert_eq!(bcrypt_calibrate(Duration::ZERO).unwrap(), MIN_COST);
+        assert!(bcrypt_calibrate(Duration::from_millis(50)).unwrap() >= MIN_COST);
+    }
+
+    #[test]
+    fn test_x25519_encrypt_decrypt() {
+        let (secret, public) = x25519_generate();
+        let encrypted_bytes: Vec<u8> = x25519_encrypt(PLAINTEXT.as_bytes(), &public).unwrap();
+        let decrypted_bytes: Vec<u8> = x25519_decrypt(&encrypted_bytes, &secret).unwrap();
+        assert_eq!(decrypted_bytes, PLAINTEXT.as_bytes());
+    }
+
+    #[test]
+    fn test_x25519_decrypt_bad_secret() {
+        let (_, public) = x25519_generate();
+        let (other_secret, _) = x25519_generate();
+        let encrypted_bytes: Vec<u8> = x25519_encrypt(PLAINTEXT.as_bytes(), &public).unwrap();
+        assert!(x25519_decrypt(&encrypted_bytes, &other_secret).is_err());
+    }
 }
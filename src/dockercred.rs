@@ -0,0 +1,98 @@
+use serde_json::{json, Value};
+
+use crate::errors::{Result, SrpkError::CredentialInvalid};
+
+/// Name docker runs for `"credsStore": "srpk"` in `~/.docker/config.json`.
+pub const PROGRAM: &str = "docker-credential-srpk";
+
+/// What docker expects to be printed when a registry has no credentials.
+pub const NOT_FOUND: &str = "credentials not found in native keychain";
+
+/// Prefix of every key holding a docker credential.
+const KEY_PREFIX: &str = "docker/";
+
+/// A registry login, as docker sends it to `store` and expects it back from `get`.
+#[derive(Debug, PartialEq)]
+pub struct Credential {
+    pub server: String,
+    pub username: String,
+    pub secret: String,
+}
+
+impl Credential {
+    /// Read the JSON docker sends to `store`.
+    ///
+    /// Returns `Err(CredentialInvalid)` if a field is missing.
+    pub fn from_json(text: &str) -> Result<Self> {
+        let value: Value =
+            serde_json::from_str(text).map_err(|e| CredentialInvalid(e.to_string()))?;
+        let field = |name: &str| -> Result<String> {
+            value[name]
+                .as_str()
+                .map(str::to_owned)
+                .ok_or_else(|| CredentialInvalid(format!("missing {}", name)))
+        };
+        Ok(Self {
+            server: field("ServerURL")?,
+            username: field("Username")?,
+            secret: field("Secret")?,
+        })
+    }
+
+    /// Render the JSON docker expects from `get`.
+    pub fn to_json(&self) -> String {
+        json!({
+            "ServerURL": self.server,
+            "Username": self.username,
+            "Secret": self.secret,
+        })
+        .to_string()
+    }
+}
+
+/// Read the registry docker sends to `get` and `erase`.
+///
+/// Returns `Err(CredentialInvalid)` if it is empty.
+pub fn read_server(text: &str) -> Result<String> {
+    match text.trim() {
+        "" => Err(CredentialInvalid("missing server URL".to_owned())),
+        server => Ok(server.to_owned()),
+    }
+}
+
+/// Get the vault key for `server`: `docker/<server>`.
+pub fn key(server: &str) -> String {
+    format!("{}{}", KEY_PREFIX, server)
+}
+
+/// Get the server a vault key belongs to, or `None` if it is not a docker credential.
+pub fn server(key: &str) -> Option<&str> {
+    key.strip_prefix(KEY_PREFIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_credential_json() {
+        let text: &str =
+            r#"{"ServerURL":"https://index.docker.io/v1/","Username":"me","Secret":"tok"}"#;
+        let credential: Credential = Credential::from_json(text).unwrap();
+        assert_eq!(credential.server, "https://index.docker.io/v1/");
+        assert_eq!(
+            Credential::from_json(&credential.to_json()).unwrap(),
+            credential
+        );
+        assert!(Credential::from_json(r#"{"ServerURL":"ghcr.io","Username":"me"}"#).is_err());
+        assert!(Credential::from_json("ghcr.io").is_err());
+    }
+
+    #[test]
+    fn test_key() {
+        assert_eq!(read_server("ghcr.io\n").unwrap(), "ghcr.io");
+        assert!(read_server("\n").is_err());
+        assert_eq!(server(&key("ghcr.io")), Some("ghcr.io"));
+        assert_eq!(server("git/ghcr.io"), None);
+    }
+}
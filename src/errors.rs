@@ -23,9 +23,61 @@ pub enum SrpkError {
     #[error("io error: {0}")]
     IOError(#[from] std::io::Error),
 
-    /// Clipboard Error
-    #[error("arboard error: {0}")]
-    ArboardError(#[from] arboard::Error),
+    /// No clipboard backend worked
+    #[error("no clipboard available ({0}); try `srpk config clipboard_backend osc52` over ssh")]
+    NoClipboard(String),
+
+    /// Background process clearing the clipboard failed to start
+    #[error("clipboard helper failed: {0}")]
+    ClipboardHelper(String),
+
+    /// Password could not be read from a file, file descriptor or command
+    #[error("could not get password: {0}")]
+    PasswordInput(String),
+
+    /// Command given to `srpk run` could not be started
+    #[error("could not run {0}: {1}")]
+    RunFailed(String, String),
+
+    /// Template has a reference that cannot be read
+    #[error("template line {0}: {1}")]
+    TemplateInvalid(usize, String),
+
+    /// Key exists but has no such metadata field
+    #[error("key {0} has no {1}")]
+    FieldNonExist(String, String),
+
+    /// Credential helper was sent something it cannot read
+    #[error("invalid credential: {0}")]
+    CredentialInvalid(String),
+
+    /// SSH private key cannot be read or used
+    #[error("invalid ssh key: {0}")]
+    SshKeyInvalid(String),
+
+    /// SSH agent cannot start
+    #[error("ssh agent failed: {0}")]
+    AgentFailed(String),
+
+    /// Export from another password manager cannot be read
+    #[error("cannot import: {0}")]
+    ImportInvalid(String),
+
+    /// Merge has conflicts and no way to ask how to resolve them
+    #[error("{0} conflict(s) need resolving; run srpk merge in a terminal or pass --on-conflict")]
+    MergeConflict(usize),
+
+    /// Vault has no sync repository
+    #[error("vault is not synced; run srpk sync init <remote> first")]
+    NoSync,
+
+    /// Operation log in a sync folder cannot be read
+    #[error("cannot read sync log: {0}")]
+    SyncLogInvalid(String),
+
+    /// Secret would be printed to a terminal
+    #[error("refusing to print a secret to a terminal; pass --force to do it anyway")]
+    StdoutTerminal,
 
     /// File path is taken
     #[error("path is occupied: {0}")]
@@ -35,10 +87,22 @@ pub enum SrpkError {
     #[error("file not found: {0}")]
     PathEmpty(PathBuf),
 
+    /// Temporary database from an interrupted session is still on disk
+    #[error("found leftover unencrypted {0} from an interrupted session")]
+    TempStale(PathBuf),
+
+    /// Vault is open in another srpk process
+    #[error("vault is in use by another srpk process ({0} is locked)")]
+    VaultInUse(PathBuf),
+
     /// UTF8Decode failed
     #[error("utf8 decode failed: {0}")]
     UTF8Decode(#[from] std::string::FromUtf8Error),
 
+    /// Vault file is not in a format srpk understands
+    #[error("vault is malformed: {0}")]
+    VaultMalformed(String),
+
     // general
     /// Missing parameter
     #[error("missing parameter")]
@@ -56,10 +120,74 @@ pub enum SrpkError {
     #[error("vault has no key {0}")]
     KeyNonExist(String),
 
+    /// Audit log events do not chain from the ones before them
+    #[error("audit log was changed or had events removed at event {0}")]
+    AuditBroken(usize),
+
+    /// Vault header cannot list another slot
+    #[error("vault already has the most slots it can (255)")]
+    SlotsFull,
+
+    /// Key is not in the trash
+    #[error("trash has no key {0}")]
+    TrashNonExist(String),
+
     /// Name is reserved
     #[error("cannot use reserved term {0}")]
     KeyReserved(String),
 
+    /// Vault database is newer than this version of srpk
+    #[error("vault uses schema version {0}, which is newer than this srpk supports")]
+    SchemaNewer(usize),
+
+    /// Vault failed `srpk verify`
+    #[error("vault failed verification with {0} problem(s)")]
+    VerifyFailed(usize),
+
+    /// Public key or identity could not be decoded
+    #[error("invalid recipient or identity: {0}")]
+    RecipientInvalid(String),
+
+    /// Duplicate recipient
+    #[error("vault already has recipient {0}")]
+    RecipientDuplicate(String),
+
+    /// Recipient does not exist
+    #[error("vault has no recipient {0}")]
+    RecipientNonExist(String),
+
+    /// Vault cannot be opened with the given identity
+    #[error("vault has no recipient slot for this identity")]
+    IdentityNoSlot,
+
+    /// Recovery share is damaged or does not belong with the others
+    #[error("invalid recovery share: {0}")]
+    ShareInvalid(String),
+
+    /// Recovery shares combined into the wrong key
+    #[error("recovery shares do not reconstruct the vault key")]
+    ShareMismatch,
+
+    /// Recovery threshold and share count do not make sense
+    #[error("need 2 <= threshold <= shares <= 255")]
+    ShareThreshold,
+
+    /// bcrypt cost outside of what bcrypt accepts
+    #[error("cost must be between 5 and 31")]
+    CostRange,
+
+    /// Flag is missing its' value or the value is not valid
+    #[error("invalid value for flag {0}")]
+    FlagInvalid(String),
+
+    /// Setting is not one srpk knows about
+    #[error("unknown setting {0}")]
+    SettingUnknown(String),
+
+    /// Setting has a value srpk cannot use
+    #[error("invalid value for setting {0}: {1}")]
+    SettingInvalid(String, String),
+
     /// Failed to find either user config_dir or user home_dir
     #[error("cannot find config directory or home directory to store active vault")]
     ConfigDir,
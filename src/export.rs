@@ -0,0 +1,207 @@
+use serde_json::{json, Map, Value};
+use std::{
+    collections::BTreeSet,
+    io::{self, Write},
+    str::FromStr,
+};
+
+use crate::errors::Result;
+
+/// Fields written as their own CSV columns, in this order, before any other metadata.
+const COLUMNS: [&str; 4] = ["name", "username", "password", "url"];
+
+/// Metadata fields written to the column of the same name in `COLUMNS`.
+const MAPPED: [&str; 2] = ["username", "url"];
+
+/// Prefix of the column for a metadata field whose name clashes with a column,
+/// which `import --format generic` takes off again.
+const META_PREFIX: &str = "meta:";
+
+/// Get the column for metadata `field`, when it is not in `MAPPED`.
+///
+/// Columns are matched ignoring case when imported, so clashes are too.
+fn column(field: &str) -> String {
+    let lower: String = field.to_lowercase();
+    match COLUMNS.contains(&lower.as_str()) || field.starts_with(META_PREFIX) {
+        true => format!("{}{}", META_PREFIX, field),
+        false => field.to_owned(),
+    }
+}
+
+/// Formats a vault can be exported as.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    /// An array of `{"key", "value", "meta"}` objects.
+    Json,
+    /// Columns `name`, `username`, `password` and `url`, then a column for every other
+    /// metadata field, prefixed with `meta:` if it would clash with another column,
+    /// which `import --format generic` reads back.
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = ();
+
+    fn from_str(s: &str) -> core::result::Result<Self, ()> {
+        match s {
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A key in the vault, with its' password and metadata.
+#[derive(Debug, PartialEq)]
+pub struct Record {
+    pub key: String,
+    pub value: String,
+    pub meta: Vec<(String, String)>,
+}
+
+impl Record {
+    fn field(&self, field: &str) -> &str {
+        self.meta
+            .iter()
+            .find(|(f, _)| f == field)
+            .map_or("", |(_, v)| v.as_str())
+    }
+}
+
+/// Write `records` to `output` in `format`, one at a time.
+pub fn write(records: &[Record], format: Format, mut output: impl Write) -> Result<()> {
+    match format {
+        Format::Json => {
+            write!(output, "[")?;
+            for (i, record) in records.iter().enumerate() {
+                let meta: Map<String, Value> = record
+                    .meta
+                    .iter()
+                    .map(|(f, v)| (f.clone(), Value::from(v.as_str())))
+                    .collect();
+                let object: Value = json!({
+                    "key": record.key,
+                    "value": record.value,
+                    "meta": meta,
+                });
+                let comma: &str = if i == 0 { "" } else { "," };
+                write!(output, "{}\n  {}", comma, object)?;
+            }
+            writeln!(output, "\n]")?;
+        }
+        Format::Csv => {
+            let extra: BTreeSet<&str> = records
+                .iter()
+                .flat_map(|r| r.meta.iter().map(|(f, _)| f.as_str()))
+                .filter(|f| !MAPPED.contains(f))
+                .collect();
+            let mut writer = csv::Writer::from_writer(&mut output);
+            let header: Vec<String> = COLUMNS
+                .iter()
+                .map(|c| c.to_string())
+                .chain(extra.iter().map(|f| column(f)))
+                .collect();
+            writer.write_record(header).map_err(io::Error::from)?;
+            for record in records {
+                let mut row: Vec<&str> = vec![
+                    &record.key,
+                    record.field("username"),
+                    &record.value,
+                    record.field("url"),
+                ];
+                row.extend(extra.iter().map(|f| record.field(f)));
+                writer.write_record(row).map_err(io::Error::from)?;
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(output.flush()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import;
+
+    fn records() -> Vec<Record> {
+        vec![
+            Record {
+                key: "github".to_owned(),
+                value: "hunter2".to_owned(),
+                meta: vec![
+                    ("notes".to_owned(), "line one\nline two".to_owned()),
+                    ("username".to_owned(), "me".to_owned()),
+                ],
+            },
+            Record {
+                key: "mail".to_owned(),
+                value: "s3cret, \"quoted\"".to_owned(),
+                meta: vec![("url".to_owned(), "https://mail.example.com".to_owned())],
+            },
+        ]
+    }
+
+    #[test]
+    fn test_write_json() {
+        let mut output: Vec<u8> = Vec::new();
+        write(&records(), Format::Json, &mut output).unwrap();
+        let value: Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(value[0]["key"], "github");
+        assert_eq!(value[0]["meta"]["notes"], "line one\nline two");
+        assert_eq!(value[1]["value"], "s3cret, \"quoted\"");
+
+        let mut output: Vec<u8> = Vec::new();
+        write(&[], Format::Json, &mut output).unwrap();
+        assert_eq!(serde_json::from_slice::<Value>(&output).unwrap(), json!([]));
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let mut output: Vec<u8> = Vec::new();
+        write(&records(), Format::Csv, &mut output).unwrap();
+        assert!(output.starts_with(b"name,username,password,url,notes\n"));
+
+        let (entries, notes) =
+            import::read(output.as_slice(), import::Format::Generic, &[]).unwrap();
+        assert!(notes.is_empty());
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].username.as_deref(), Some("me"));
+        assert_eq!(
+            entries[0].meta,
+            vec![("notes".to_owned(), "line one\nline two".to_owned())]
+        );
+        assert_eq!(entries[1].password, "s3cret, \"quoted\"");
+        assert_eq!(entries[1].url.as_deref(), Some("https://mail.example.com"));
+        assert!(entries[1].meta.is_empty());
+    }
+
+    #[test]
+    fn test_write_csv_clashing() {
+        let records: Vec<Record> = vec![Record {
+            key: "bank".to_owned(),
+            value: "hunter2".to_owned(),
+            meta: vec![
+                ("meta:pin".to_owned(), "1".to_owned()),
+                ("name".to_owned(), "Jo Bloggs".to_owned()),
+                ("password".to_owned(), "old".to_owned()),
+                ("URL".to_owned(), "https://bank.example.com".to_owned()),
+            ],
+        }];
+        let mut output: Vec<u8> = Vec::new();
+        write(&records, Format::Csv, &mut output).unwrap();
+        let text: &str = std::str::from_utf8(&output).unwrap();
+        assert_eq!(
+            text,
+            "name,username,password,url,meta:URL,meta:meta:pin,meta:name,meta:password\n\
+             bank,,hunter2,,https://bank.example.com,1,Jo Bloggs,old\n"
+        );
+
+        let (entries, _) = import::read(output.as_slice(), import::Format::Generic, &[]).unwrap();
+        assert_eq!(entries[0].name, "bank");
+        assert_eq!(entries[0].password, "hunter2");
+        assert_eq!(entries[0].url, None);
+        let mut expected: Vec<(String, String)> = records[0].meta.clone();
+        expected.sort();
+        assert_eq!(entries[0].meta, expected);
+    }
+}
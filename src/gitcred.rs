@@ -0,0 +1,103 @@
+use std::io::{BufRead, Write};
+
+use crate::errors::Result;
+
+/// Name git runs for `git config credential.helper srpk`.
+pub const PROGRAM: &str = "git-credential-srpk";
+
+/// Prefix of every key holding a git credential.
+const KEY_PREFIX: &str = "git/";
+
+/// A credential description, as sent by git to a credential helper.
+#[derive(Default, Debug, PartialEq)]
+pub struct Credential {
+    pub host: Option<String>,
+    pub path: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl Credential {
+    /// Read `name=value` lines from git until a blank line or the end of input.
+    ///
+    /// Attributes srpk does not use, such as `protocol`, are ignored.
+    pub fn read(input: impl BufRead) -> Result<Self> {
+        let mut credential: Self = Self::default();
+        for line in input.lines() {
+            let line: String = line?;
+            if line.is_empty() {
+                break;
+            }
+            let Some((name, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value: Option<String> = Some(value.to_owned());
+            match name {
+                "host" => credential.host = value,
+                "path" => credential.path = value,
+                "username" => credential.username = value,
+                "password" => credential.password = value,
+                _ => {}
+            }
+        }
+        Ok(credential)
+    }
+
+    /// Get the vault key for this credential: `git/<host>`, or `git/<host>/<path>`
+    /// if git sends paths (`credential.useHttpPath`).
+    ///
+    /// Returns `None` if git did not send a host.
+    pub fn key(&self) -> Option<String> {
+        let host: &str = self.host.as_deref().filter(|h| !h.is_empty())?;
+        Some(match self.path.as_deref().map(|p| p.trim_matches('/')) {
+            Some(path) if !path.is_empty() => format!("{}{}/{}", KEY_PREFIX, host, path),
+            _ => format!("{}{}", KEY_PREFIX, host),
+        })
+    }
+
+    /// Answer git with the username and password.
+    pub fn write(&self, mut output: impl Write) -> Result<()> {
+        if let Some(username) = &self.username {
+            writeln!(output, "username={}", username)?;
+        }
+        if let Some(password) = &self.password {
+            writeln!(output, "password={}", password)?;
+        }
+        Ok(output.flush()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read() {
+        let input: &[u8] =
+            b"protocol=https\nhost=example.com\npath=me/repo.git\nusername=me\n\nhost=ignored\n";
+        let credential: Credential = Credential::read(input).unwrap();
+        assert_eq!(credential.host.as_deref(), Some("example.com"));
+        assert_eq!(credential.username.as_deref(), Some("me"));
+        assert!(credential.password.is_none());
+        assert_eq!(credential.key().unwrap(), "git/example.com/me/repo.git");
+
+        let credential: Credential = Credential::read(&b"host=example.com:8080\n"[..]).unwrap();
+        assert_eq!(credential.key().unwrap(), "git/example.com:8080");
+        assert!(Credential::read(&b"protocol=file\n"[..])
+            .unwrap()
+            .key()
+            .is_none());
+    }
+
+    #[test]
+    fn test_write() {
+        let credential: Credential = Credential {
+            username: Some("me".to_owned()),
+            password: Some("token".to_owned()),
+            ..Default::default()
+        };
+        let mut output: Vec<u8> = Vec::new();
+        credential.write(&mut output).unwrap();
+        assert_eq!(output, b"username=me\npassword=token\n");
+    }
+}
@@ -0,0 +1,177 @@
+use crate::errors::{
+    Result,
+    SrpkError::{SlotsFull, VaultMalformed},
+};
+
+/// Marks a vault using the slotted format.
+///
+/// Legacy vaults start with their bcrypt cost (5-31), so they never collide with this.
+const MAGIC: &[u8; 4] = b"SRPK";
+/// Format version written, whose body is sealed with the header as associated data.
+const VERSION: u8 = 1;
+
+/// Most slots a header can list.
+pub const MAX_SLOTS: usize = u8::MAX as usize;
+
+const SLOT_PASSWORD: u8 = 1;
+const SLOT_X25519: u8 = 2;
+
+/// A way of unlocking the data key of a vault.
+#[derive(Clone)]
+pub enum Slot {
+    /// The data key, encrypted by `aes256_encrypt` using the vault password.
+    Password(Vec<u8>),
+    /// The data key, encrypted by `x25519_encrypt` to `recipient`.
+    X25519 {
+        recipient: [u8; 32],
+        payload: Vec<u8>,
+    },
+}
+
+/// A parsed vault file: its' header, the associated data its' body is sealed with, and the body.
+pub type Parsed<'a> = (Header, &'a [u8], &'a [u8]);
+
+/// The unencrypted start of a vault file, listing every slot that can unlock it.
+pub struct Header {
+    pub slots: Vec<Slot>,
+}
+
+impl Header {
+    /// Split a vault file into its `Header`, the associated data the body is sealed with
+    /// (the header as written), and the encrypted body.
+    ///
+    /// Returns `None` if `bytes` is a legacy (password-only) vault.
+    pub fn parse(bytes: &[u8]) -> Result<Option<Parsed<'_>>> {
+        if !bytes.starts_with(MAGIC) {
+            return Ok(None);
+        }
+        let mut rest: &[u8] = &bytes[MAGIC.len()..];
+        let version: u8 = take(&mut rest, 1)?[0];
+        if version != VERSION {
+            return Err(VaultMalformed(format!(
+                "unknown format version {}",
+                version
+            )));
+        }
+
+        let count: u8 = take(&mut rest, 1)?[0];
+        let mut slots: Vec<Slot> = Vec::new();
+        for _ in 0..count {
+            let kind: u8 = take(&mut rest, 1)?[0];
+            let len: usize = u16::from_be_bytes(take(&mut rest, 2)?.try_into().unwrap()) as usize;
+            let data: &[u8] = take(&mut rest, len)?;
+            slots.push(match kind {
+                SLOT_PASSWORD => Slot::Password(data.to_vec()),
+                SLOT_X25519 => {
+                    if data.len() < 32 {
+                        return Err(VaultMalformed("truncated x25519 slot".to_string()));
+                    }
+                    Slot::X25519 {
+                        recipient: data[..32].try_into().unwrap(),
+                        payload: data[32..].to_vec(),
+                    }
+                }
+                _ => return Err(VaultMalformed(format!("unknown slot type {}", kind))),
+            });
+        }
+
+        Ok(Some((
+            Self { slots },
+            &bytes[..bytes.len() - rest.len()],
+            rest,
+        )))
+    }
+
+    /// Turn the header into its' on-disk form.
+    ///
+    /// Returns `Err(SlotsFull)` if there are more than `MAX_SLOTS` slots.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        if self.slots.len() > MAX_SLOTS {
+            return Err(SlotsFull);
+        }
+        let mut bytes: Vec<u8> = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.push(self.slots.len() as u8);
+        for slot in &self.slots {
+            let (kind, data): (u8, Vec<u8>) = match slot {
+                Slot::Password(payload) => (SLOT_PASSWORD, payload.clone()),
+                Slot::X25519 { recipient, payload } => {
+                    (SLOT_X25519, [recipient.to_vec(), payload.clone()].concat())
+                }
+            };
+            bytes.push(kind);
+            bytes.extend_from_slice(&(data.len() as u16).to_be_bytes());
+            bytes.extend_from_slice(&data);
+        }
+        Ok(bytes)
+    }
+}
+
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if bytes.len() < len {
+        return Err(VaultMalformed("header is truncated".to_string()));
+    }
+    let (head, tail) = bytes.split_at(len);
+    *bytes = tail;
+    Ok(head)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let header: Header = Header {
+            slots: vec![
+                Slot::Password(vec![1, 2, 3]),
+                Slot::X25519 {
+                    recipient: [7u8; 32],
+                    payload: vec![4, 5],
+                },
+            ],
+        };
+        let written: Vec<u8> = header.to_bytes().unwrap();
+        let bytes: Vec<u8> = [written.clone(), vec![9, 9]].concat();
+        let (parsed, aad, body) = Header::parse(&bytes).unwrap().unwrap();
+        assert_eq!(aad, written);
+        assert_eq!(parsed.slots.len(), 2);
+        assert!(matches!(&parsed.slots[0], Slot::Password(p) if p == &vec![1, 2, 3]));
+        assert!(
+            matches!(&parsed.slots[1], Slot::X25519 { recipient, payload } if recipient == &[7u8; 32] && payload == &vec![4, 5])
+        );
+        assert_eq!(body, &[9, 9]);
+
+        let mut newer: Vec<u8> = bytes.clone();
+        newer[MAGIC.len()] = VERSION + 1;
+        assert!(Header::parse(&newer).is_err());
+    }
+
+    #[test]
+    fn test_slots_full() {
+        let mut header: Header = Header {
+            slots: vec![Slot::Password(vec![1]); MAX_SLOTS],
+        };
+        let bytes: Vec<u8> = header.to_bytes().unwrap();
+        assert_eq!(
+            Header::parse(&bytes).unwrap().unwrap().0.slots.len(),
+            MAX_SLOTS
+        );
+        header.slots.push(Slot::Password(vec![1]));
+        assert!(header.to_bytes().is_err());
+    }
+
+    #[test]
+    fn test_legacy() {
+        assert!(Header::parse(&[12u8, 0, 0]).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_truncated() {
+        let header: Header = Header {
+            slots: vec![Slot::Password(vec![1, 2, 3])],
+        };
+        let bytes: Vec<u8> = header.to_bytes().unwrap();
+        assert!(Header::parse(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,404 @@
+use std::{collections::HashSet, io::Read, str::FromStr};
+
+use crate::errors::{Result, SrpkError::ImportInvalid};
+
+/// Password managers whose CSV exports can be imported.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    Bitwarden,
+    Lastpass,
+    OnePassword,
+    Chrome,
+    Firefox,
+    /// A KeePass database rather than a CSV export; see `kdbx::read`.
+    Kdbx,
+    /// A `pass` password store directory rather than a CSV export; see `passstore::read`.
+    Pass,
+    /// Columns named `name`, `username`, `password` and `url`, or as given with `--map`,
+    /// as `export --format csv` writes; other columns are read back with any `meta:` taken off.
+    Generic,
+}
+
+impl FromStr for Format {
+    type Err = ();
+
+    fn from_str(s: &str) -> core::result::Result<Self, ()> {
+        match s {
+            "bitwarden" => Ok(Format::Bitwarden),
+            "lastpass" => Ok(Format::Lastpass),
+            "1password" => Ok(Format::OnePassword),
+            "chrome" => Ok(Format::Chrome),
+            "firefox" => Ok(Format::Firefox),
+            "kdbx" => Ok(Format::Kdbx),
+            "pass" => Ok(Format::Pass),
+            "generic" => Ok(Format::Generic),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Fields of an entry that can be read from a column.
+const FIELDS: [&str; 4] = ["name", "username", "password", "url"];
+
+impl Format {
+    /// Get the column each of `FIELDS` is read from in this format's export.
+    fn columns(&self) -> [Option<&'static str>; 4] {
+        match self {
+            Format::Bitwarden => [
+                Some("name"),
+                Some("login_username"),
+                Some("login_password"),
+                Some("login_uri"),
+            ],
+            Format::Lastpass | Format::Chrome | Format::Generic | Format::Kdbx | Format::Pass => [
+                Some("name"),
+                Some("username"),
+                Some("password"),
+                Some("url"),
+            ],
+            Format::OnePassword => [
+                Some("title"),
+                Some("username"),
+                Some("password"),
+                Some("url"),
+            ],
+            // firefox does not name logins, so they are named after their site
+            Format::Firefox => [None, Some("username"), Some("password"), Some("url")],
+        }
+    }
+
+    /// Get the column this format's export keeps notes in.
+    fn notes(&self) -> &'static str {
+        match self {
+            Format::Lastpass => "extra",
+            Format::Chrome => "note",
+            _ => "notes",
+        }
+    }
+
+    /// Get the columns of this format's export that only hold its' own bookkeeping.
+    fn ignored(&self) -> &'static [&'static str] {
+        match self {
+            Format::Bitwarden => &["favorite", "type", "reprompt"],
+            Format::Lastpass => &["fav"],
+            Format::Firefox => &[
+                "httprealm",
+                "formactionorigin",
+                "guid",
+                "timecreated",
+                "timelastused",
+                "timepasswordchanged",
+            ],
+            _ => &[],
+        }
+    }
+}
+
+/// A login read from an export.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub name: String,
+    pub username: Option<String>,
+    pub password: String,
+    pub url: Option<String>,
+    /// Any other metadata fields, such as `notes`.
+    pub meta: Vec<(String, String)>,
+}
+
+/// Get the host of `url`, to name logins that have no name.
+fn host(url: &str) -> &str {
+    let rest: &str = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let host: &str = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+    host.rsplit_once('@').map_or(host, |(_, host)| host)
+}
+
+/// Read the logins in the CSV export `input`.
+///
+/// `map` overrides which column a field is read from, as `(field, column)` pairs.
+/// Columns are matched ignoring case. Any other non-empty column is kept as metadata,
+/// with the format's notes column as `notes`.
+///
+/// Rows without a password, such as notes, are skipped, and returned as notes for the user.
+///
+/// Returns `Err(ImportInvalid)` if the file cannot be read or is missing a column.
+pub fn read(
+    input: impl Read,
+    format: Format,
+    map: &[(String, String)],
+) -> Result<(Vec<Entry>, Vec<String>)> {
+    let mut columns: Vec<Option<String>> = format
+        .columns()
+        .iter()
+        .map(|c| c.map(str::to_owned))
+        .collect();
+    for (field, column) in map {
+        match FIELDS.iter().position(|f| f == field) {
+            Some(i) => columns[i] = Some(column.to_lowercase()),
+            None => return Err(ImportInvalid(format!("unknown field {} in --map", field))),
+        }
+    }
+
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(input);
+    let raw: Vec<String> = reader
+        .headers()
+        .map_err(|e| ImportInvalid(e.to_string()))?
+        .iter()
+        .map(|h| h.trim().to_owned())
+        .collect();
+    let headers: Vec<String> = raw.iter().map(|h| h.to_lowercase()).collect();
+    let mut index: Vec<Option<usize>> = Vec::new();
+    for (field, column) in FIELDS.iter().zip(&columns) {
+        let Some(column) = column else {
+            index.push(None);
+            continue;
+        };
+        match headers.iter().position(|h| h == column) {
+            Some(i) => index.push(Some(i)),
+            None if *field == "password" || *field == "name" => {
+                return Err(ImportInvalid(format!("no {} column", column)))
+            }
+            None => index.push(None),
+        }
+    }
+    let extra: Vec<(usize, String)> = headers
+        .iter()
+        .enumerate()
+        .filter(|(i, h)| !index.contains(&Some(*i)) && !format.ignored().contains(&h.as_str()))
+        .map(|(i, h)| match format {
+            Format::Generic => (
+                i,
+                raw[i].strip_prefix("meta:").unwrap_or(&raw[i]).to_owned(),
+            ),
+            _ if h == format.notes() => (i, "notes".to_owned()),
+            _ => (i, raw[i].clone()),
+        })
+        .collect();
+
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut notes: Vec<String> = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| ImportInvalid(e.to_string()))?;
+        let get = |i: usize| -> Option<String> {
+            index[i]
+                .and_then(|i| record.get(i))
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_owned)
+        };
+        let line: u64 = record.position().map_or(0, |p| p.line());
+        let url: Option<String> = get(3);
+        let name: Option<String> = get(0).or_else(|| url.as_deref().map(|u| host(u).to_owned()));
+        let Some(name) = name.filter(|n| !n.is_empty()) else {
+            notes.push(format!("line {} has no name and was not imported", line));
+            continue;
+        };
+        let Some(password) = get(2) else {
+            notes.push(format!("{} has no password and was not imported", name));
+            continue;
+        };
+        let meta: Vec<(String, String)> = extra
+            .iter()
+            .filter_map(|(i, field)| {
+                let value: &str = record.get(*i)?.trim();
+                (!value.is_empty()).then(|| (field.clone(), value.to_owned()))
+            })
+            .collect();
+        entries.push(Entry {
+            name,
+            username: get(1),
+            password,
+            url,
+            meta,
+        });
+    }
+    Ok((entries, notes))
+}
+
+/// What to do with an entry whose name is already a key.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Policy {
+    Skip,
+    Rename,
+    Overwrite,
+}
+
+impl FromStr for Policy {
+    type Err = ();
+
+    fn from_str(s: &str) -> core::result::Result<Self, ()> {
+        match s {
+            "skip" => Ok(Policy::Skip),
+            "rename" => Ok(Policy::Rename),
+            "overwrite" => Ok(Policy::Overwrite),
+            _ => Err(()),
+        }
+    }
+}
+
+/// What will be done with an entry.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    /// Added as a new key.
+    Add,
+    /// Left out, as a key already has its' name.
+    Skip,
+    /// Added as a new key, with a different name.
+    Rename,
+    /// Replaces the key that has its' name.
+    Overwrite,
+}
+
+/// An entry and what will be done with it.
+#[derive(Debug)]
+pub struct Planned {
+    pub entry: Entry,
+    pub key: String,
+    pub action: Action,
+}
+
+/// Decide what to do with each of `entries`, given the keys already in the vault,
+/// following `policy` when a name is taken, including by an earlier entry.
+///
+/// Names in `reserved` cannot be keys, so those entries are always renamed.
+pub fn plan(
+    entries: Vec<Entry>,
+    existing: &[String],
+    reserved: &[&str],
+    policy: Policy,
+) -> Vec<Planned> {
+    let mut taken: HashSet<String> = existing.iter().cloned().collect();
+    let mut planned: Vec<Planned> = Vec::new();
+    for entry in entries {
+        let is_reserved: bool = reserved.contains(&entry.name.as_str());
+        let policy: Policy = match is_reserved {
+            true => Policy::Rename,
+            false => policy,
+        };
+        let (key, action): (String, Action) = if !is_reserved && !taken.contains(&entry.name) {
+            (entry.name.clone(), Action::Add)
+        } else {
+            match policy {
+                Policy::Skip => (entry.name.clone(), Action::Skip),
+                Policy::Overwrite => (entry.name.clone(), Action::Overwrite),
+                Policy::Rename => {
+                    let key: String = (2..)
+                        .map(|n| format!("{}-{}", entry.name, n))
+                        .find(|k| !taken.contains(k))
+                        .unwrap();
+                    (key, Action::Rename)
+                }
+            }
+        };
+        taken.insert(key.clone());
+        planned.push(Planned { entry, key, action });
+    }
+    planned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str) -> Entry {
+        Entry {
+            name: name.to_owned(),
+            username: None,
+            password: "pass".to_owned(),
+            url: None,
+            meta: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_read() {
+        let bitwarden: &[u8] = b"folder,favorite,type,name,notes,fields,reprompt,login_uri,login_username,login_password,login_totp\n\
+            ,,login,github,two factor,,0,https://github.com,me,hunter2,\n\
+            ,,note,shopping list,eggs,,0,,,,\n";
+        let (entries, notes) = read(bitwarden, Format::Bitwarden, &[]).unwrap();
+        assert_eq!(
+            entries,
+            vec![Entry {
+                name: "github".to_owned(),
+                username: Some("me".to_owned()),
+                password: "hunter2".to_owned(),
+                url: Some("https://github.com".to_owned()),
+                meta: vec![("notes".to_owned(), "two factor".to_owned())],
+            }]
+        );
+        assert_eq!(
+            notes,
+            vec!["shopping list has no password and was not imported".to_owned()]
+        );
+
+        let firefox: &[u8] = b"\"url\",\"username\",\"password\",\"httpRealm\"\n\
+            \"https://me@example.com:8443/login\",\"me\",\"hunter2\",\"\"\n\
+            \"\",\"me\",\"hunter2\",\"\"\n";
+        let (entries, notes) = read(firefox, Format::Firefox, &[]).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "example.com:8443");
+        assert!(entries[0].meta.is_empty());
+        assert_eq!(
+            notes,
+            vec!["line 3 has no name and was not imported".to_owned()]
+        );
+
+        let lastpass: &[u8] = b"url,username,password,totp,extra,name,grouping,fav\n\
+            https://mail.example.com,me,hunter2,,\"line one\nline two\",mail,Email,1\n";
+        let (entries, _) = read(lastpass, Format::Lastpass, &[]).unwrap();
+        assert_eq!(
+            entries[0].meta,
+            vec![
+                ("notes".to_owned(), "line one\nline two".to_owned()),
+                ("grouping".to_owned(), "Email".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_map() {
+        let csv: &[u8] = b"Site,Login,Secret\nmail,me,hunter2\n";
+        assert!(read(csv, Format::Generic, &[]).is_err());
+        let map: Vec<(String, String)> = vec![
+            ("name".to_owned(), "Site".to_owned()),
+            ("username".to_owned(), "Login".to_owned()),
+            ("password".to_owned(), "Secret".to_owned()),
+        ];
+        let (entries, _) = read(csv, Format::Generic, &map).unwrap();
+        assert_eq!(entries[0].name, "mail");
+        assert_eq!(entries[0].username.as_deref(), Some("me"));
+        let map: Vec<(String, String)> = vec![("notes".to_owned(), "Site".to_owned())];
+        assert!(read(csv, Format::Generic, &map).is_err());
+    }
+
+    #[test]
+    fn test_plan() {
+        let entries: Vec<Entry> = vec![entry("github"), entry("mail"), entry("mail"), entry("ls")];
+        let existing: Vec<String> = vec!["github".to_owned(), "github-2".to_owned()];
+        let actions = |policy: Policy| -> Vec<(String, Action)> {
+            plan(entries.clone(), &existing, &["ls"], policy)
+                .into_iter()
+                .map(|p| (p.key, p.action))
+                .collect()
+        };
+
+        assert_eq!(
+            actions(Policy::Skip),
+            vec![
+                ("github".to_owned(), Action::Skip),
+                ("mail".to_owned(), Action::Add),
+                ("mail".to_owned(), Action::Skip),
+                ("ls-2".to_owned(), Action::Rename),
+            ]
+        );
+        assert_eq!(
+            actions(Policy::Rename),
+            vec![
+                ("github-3".to_owned(), Action::Rename),
+                ("mail".to_owned(), Action::Add),
+                ("mail-2".to_owned(), Action::Rename),
+                ("ls-2".to_owned(), Action::Rename),
+            ]
+        );
+        assert_eq!(actions(Policy::Overwrite)[2].1, Action::Overwrite);
+    }
+}
@@ -0,0 +1,548 @@
+use aes::{
+    cipher::{BlockDecryptMut, BlockEncrypt, KeyInit, KeyIvInit, StreamCipher},
+    Aes256,
+};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20::ChaCha20;
+use flate2::read::GzDecoder;
+use hmac::{Hmac, Mac};
+use roxmltree::{Document, Node, NodeId};
+use salsa20::Salsa20;
+use sha2::{Digest, Sha256, Sha512};
+use std::{collections::HashMap, io::Read};
+
+use crate::errors::{Result, SrpkError::ImportInvalid};
+use crate::import::Entry;
+
+const SIGNATURE: [u8; 8] = [0x03, 0xd9, 0xa2, 0x9a, 0x67, 0xfb, 0x4b, 0xb5];
+
+const CIPHER_AES256: [u8; 16] = uuid(0x31c1f2e6bf714350be5805216afc5aff);
+const CIPHER_CHACHA20: [u8; 16] = uuid(0xd6038a2b8b6f4cb5a524339a31dbb59a);
+const CIPHER_TWOFISH: [u8; 16] = uuid(0xad68f29f576f4bb9a36ad47af965346c);
+const KDF_AES: [u8; 16] = uuid(0xc9d9f39a628a4460bf740d08c18a4fea);
+const KDF_ARGON2D: [u8; 16] = uuid(0xef636ddf8c29444b91f7a9a403e30a0c);
+const KDF_ARGON2ID: [u8; 16] = uuid(0x9e298b1956db4773b23dfc3ec6f0a1e6);
+
+const INNER_SALSA20: u32 = 2;
+const INNER_CHACHA20: u32 = 3;
+const SALSA20_NONCE: [u8; 8] = [0xe8, 0x30, 0x09, 0x4b, 0x97, 0x20, 0x5d, 0x2a];
+
+const fn uuid(n: u128) -> [u8; 16] {
+    n.to_be_bytes()
+}
+
+fn invalid(problem: &str) -> crate::errors::SrpkError {
+    ImportInvalid(problem.to_owned())
+}
+
+/// Reads the little-endian fields of a KeePass database.
+struct Cursor<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < n {
+            return Err(invalid("database is truncated"));
+        }
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    /// Read a field: an id, then its' data, with a `u32` length between them.
+    fn field(&mut self) -> Result<(u8, &'a [u8])> {
+        let id: u8 = self.u8()?;
+        let len: u32 = self.u32()?;
+        Ok((id, self.take(len as usize)?))
+    }
+}
+
+/// Read a `VariantDictionary`, the format of the key derivation parameters.
+fn variant_dictionary(bytes: &[u8]) -> Result<HashMap<String, Vec<u8>>> {
+    let mut cursor: Cursor = Cursor { bytes };
+    if cursor.take(2)?[1] != 1 {
+        return Err(invalid("unknown key derivation parameter version"));
+    }
+    let mut items: HashMap<String, Vec<u8>> = HashMap::new();
+    loop {
+        let kind: u8 = cursor.u8()?;
+        if kind == 0 {
+            return Ok(items);
+        }
+        let len: u32 = cursor.u32()?;
+        let key: String = String::from_utf8_lossy(cursor.take(len as usize)?).into_owned();
+        let len: u32 = cursor.u32()?;
+        items.insert(key, cursor.take(len as usize)?.to_vec());
+    }
+}
+
+fn param_u64(params: &HashMap<String, Vec<u8>>, name: &str) -> Result<u64> {
+    match params.get(name).map(|v| v.as_slice()) {
+        Some(v) if v.len() == 8 => Ok(u64::from_le_bytes(v.try_into().unwrap())),
+        Some(v) if v.len() == 4 => Ok(u32::from_le_bytes(v.try_into().unwrap()) as u64),
+        _ => Err(ImportInvalid(format!(
+            "missing key derivation parameter {}",
+            name
+        ))),
+    }
+}
+
+/// Turn the composite key into the transformed key, as the database's parameters say.
+fn derive(params: &HashMap<String, Vec<u8>>, composite: &[u8; 32]) -> Result<[u8; 32]> {
+    let salt: &[u8] = params
+        .get("S")
+        .ok_or_else(|| invalid("missing key derivation salt"))?;
+    let mut transformed: [u8; 32] = [0u8; 32];
+    match params.get("$UUID").map(|v| v.as_slice()) {
+        Some(kdf) if kdf == KDF_AES => {
+            let aes: Aes256 =
+                Aes256::new_from_slice(salt).map_err(|_| invalid("bad key derivation seed"))?;
+            transformed = *composite;
+            for _ in 0..param_u64(params, "R")? {
+                for block in transformed.chunks_exact_mut(16) {
+                    aes.encrypt_block(block.into());
+                }
+            }
+            transformed = Sha256::digest(transformed).into();
+        }
+        Some(kdf) if kdf == KDF_ARGON2D || kdf == KDF_ARGON2ID => {
+            let algorithm: Algorithm = match kdf == KDF_ARGON2D {
+                true => Algorithm::Argon2d,
+                false => Algorithm::Argon2id,
+            };
+            let version: Version = match param_u64(params, "V")? {
+                0x10 => Version::V0x10,
+                _ => Version::V0x13,
+            };
+            let params: Params = Params::new(
+                (param_u64(params, "M")? / 1024) as u32,
+                param_u64(params, "I")? as u32,
+                param_u64(params, "P")? as u32,
+                Some(32),
+            )
+            .map_err(|e| ImportInvalid(e.to_string()))?;
+            Argon2::new(algorithm, version, params)
+                .hash_password_into(composite, salt, &mut transformed)
+                .map_err(|e| ImportInvalid(e.to_string()))?;
+        }
+        _ => return Err(invalid("unsupported key derivation function")),
+    }
+    Ok(transformed)
+}
+
+/// Get the HMAC key for block `index` from the database's HMAC base key.
+fn block_mac(base: &[u8], index: u64) -> Hmac<Sha256> {
+    let key = Sha512::new()
+        .chain_update(index.to_le_bytes())
+        .chain_update(base)
+        .finalize();
+    <Hmac<Sha256> as Mac>::new_from_slice(&key).unwrap()
+}
+
+/// The stream cipher protecting values such as passwords inside the XML.
+enum InnerStream {
+    Salsa20(Salsa20),
+    ChaCha20(ChaCha20),
+}
+
+impl InnerStream {
+    fn new(id: u32, key: &[u8]) -> Result<Self> {
+        match id {
+            INNER_SALSA20 => {
+                let key = Sha256::digest(key);
+                Ok(Self::Salsa20(Salsa20::new(&key, &SALSA20_NONCE.into())))
+            }
+            INNER_CHACHA20 => {
+                let hash = Sha512::digest(key);
+                Ok(Self::ChaCha20(
+                    ChaCha20::new_from_slices(&hash[..32], &hash[32..44]).unwrap(),
+                ))
+            }
+            _ => Err(invalid("unsupported inner stream cipher")),
+        }
+    }
+
+    fn apply(&mut self, data: &mut [u8]) {
+        match self {
+            Self::Salsa20(cipher) => cipher.apply_keystream(data),
+            Self::ChaCha20(cipher) => cipher.apply_keystream(data),
+        }
+    }
+}
+
+/// Open the KeePass database `bytes` with `password`, and return its' XML document,
+/// attachments, and inner stream.
+fn decrypt(bytes: &[u8], password: &str) -> Result<(String, Vec<Vec<u8>>, InnerStream)> {
+    let mut cursor: Cursor = Cursor { bytes };
+    if cursor.take(8)? != SIGNATURE {
+        return Err(invalid("not a KeePass database"));
+    }
+    let version: u32 = cursor.u32()?;
+    if version >> 16 != 4 {
+        return Err(ImportInvalid(format!(
+            "KDBX {}.{} is not supported, only KDBX 4; save the database with a recent KeePass first",
+            version >> 16,
+            version & 0xffff
+        )));
+    }
+
+    let mut cipher: Option<&[u8]> = None;
+    let mut compressed: bool = false;
+    let mut seed: Option<&[u8]> = None;
+    let mut iv: Option<&[u8]> = None;
+    let mut kdf: Option<HashMap<String, Vec<u8>>> = None;
+    loop {
+        match cursor.field()? {
+            (0, _) => break,
+            (2, data) => cipher = Some(data),
+            (3, data) => compressed = data.first() == Some(&1),
+            (4, data) => seed = Some(data),
+            (7, data) => iv = Some(data),
+            (11, data) => kdf = Some(variant_dictionary(data)?),
+            _ => {}
+        }
+    }
+    let header: &[u8] = &bytes[..bytes.len() - cursor.bytes.len()];
+    let (Some(cipher), Some(seed), Some(iv), Some(kdf)) = (cipher, seed, iv, kdf) else {
+        return Err(invalid("database header is incomplete"));
+    };
+    if cursor.take(32)? != Sha256::digest(header).as_slice() {
+        return Err(invalid("database header is corrupted"));
+    }
+    let header_mac: &[u8] = cursor.take(32)?;
+
+    let composite: [u8; 32] = Sha256::digest(Sha256::digest(password.as_bytes())).into();
+    let transformed: [u8; 32] = derive(&kdf, &composite)?;
+    let key = Sha256::new()
+        .chain_update(seed)
+        .chain_update(transformed)
+        .finalize();
+    let mac_base = Sha512::new()
+        .chain_update(seed)
+        .chain_update(transformed)
+        .chain_update([1u8])
+        .finalize();
+    if block_mac(&mac_base, u64::MAX)
+        .chain_update(header)
+        .verify_slice(header_mac)
+        .is_err()
+    {
+        return Err(invalid(
+            "wrong password, or the database needs a key file, which is not supported",
+        ));
+    }
+
+    let mut body: Vec<u8> = Vec::new();
+    for index in 0u64.. {
+        let mac: &[u8] = cursor.take(32)?;
+        let len: u32 = cursor.u32()?;
+        let block: &[u8] = cursor.take(len as usize)?;
+        block_mac(&mac_base, index)
+            .chain_update(index.to_le_bytes())
+            .chain_update(len.to_le_bytes())
+            .chain_update(block)
+            .verify_slice(mac)
+            .map_err(|_| invalid("database is corrupted"))?;
+        if len == 0 {
+            break;
+        }
+        body.extend_from_slice(block);
+    }
+
+    let body: Vec<u8> = match cipher {
+        c if c == CIPHER_AES256 => cbc::Decryptor::<Aes256>::new_from_slices(&key, iv)
+            .map_err(|_| invalid("bad encryption IV"))?
+            .decrypt_padded_vec_mut::<aes::cipher::block_padding::Pkcs7>(&body)
+            .map_err(|_| invalid("database is corrupted"))?,
+        c if c == CIPHER_CHACHA20 => {
+            ChaCha20::new_from_slices(&key, iv)
+                .map_err(|_| invalid("bad encryption IV"))?
+                .apply_keystream(&mut body);
+            body
+        }
+        c if c == CIPHER_TWOFISH => return Err(invalid("the Twofish cipher is not supported")),
+        _ => return Err(invalid("unknown cipher")),
+    };
+    let body: Vec<u8> = match compressed {
+        true => {
+            let mut out: Vec<u8> = Vec::new();
+            GzDecoder::new(body.as_slice())
+                .read_to_end(&mut out)
+                .map_err(|_| invalid("database is corrupted"))?;
+            out
+        }
+        false => body,
+    };
+
+    let mut cursor: Cursor = Cursor { bytes: &body };
+    let mut stream_id: u32 = 0;
+    let mut stream_key: &[u8] = &[];
+    let mut binaries: Vec<Vec<u8>> = Vec::new();
+    loop {
+        match cursor.field()? {
+            (0, _) => break,
+            (1, data) if data.len() == 4 => {
+                stream_id = u32::from_le_bytes(data.try_into().unwrap())
+            }
+            (2, data) => stream_key = data,
+            // the first byte holds flags for KeePass itself
+            (3, data) if !data.is_empty() => binaries.push(data[1..].to_vec()),
+            _ => {}
+        }
+    }
+    let stream: InnerStream = InnerStream::new(stream_id, stream_key)?;
+    let xml: String =
+        String::from_utf8(cursor.bytes.to_vec()).map_err(|_| invalid("database is corrupted"))?;
+    Ok((xml, binaries, stream))
+}
+
+fn child<'a, 'i>(node: Node<'a, 'i>, name: &str) -> Option<Node<'a, 'i>> {
+    node.children().find(|n| n.has_tag_name(name))
+}
+
+fn child_text(node: Node, name: &str) -> String {
+    child(node, name)
+        .and_then(|n| n.text())
+        .unwrap_or_default()
+        .to_owned()
+}
+
+/// Turns the XML of a database into entries, counting what it leaves behind.
+struct Walker<'x> {
+    protected: HashMap<NodeId, String>,
+    binaries: &'x [Vec<u8>],
+    recycle_bin: Option<String>,
+    entries: Vec<Entry>,
+    history: usize,
+    recycled: usize,
+    no_password: usize,
+    expiring: usize,
+}
+
+impl Walker<'_> {
+    fn value(&self, node: Node) -> String {
+        match self.protected.get(&node.id()) {
+            Some(value) => value.clone(),
+            None => node.text().unwrap_or_default().to_owned(),
+        }
+    }
+
+    /// Collect the entries of `group` and its' subgroups, named with `path` in front.
+    fn group(&mut self, group: Node, path: &[String]) {
+        if self.recycle_bin.is_some() && Some(child_text(group, "UUID")) == self.recycle_bin {
+            self.recycled += group
+                .descendants()
+                .filter(|n| {
+                    n.has_tag_name("Entry") && !n.ancestors().any(|a| a.has_tag_name("History"))
+                })
+                .count();
+            return;
+        }
+        for node in group.children() {
+            if node.has_tag_name("Entry") {
+                self.entry(node, path);
+            } else if node.has_tag_name("Group") {
+                let mut path: Vec<String> = path.to_vec();
+                path.push(child_text(node, "Name"));
+                self.group(node, &path);
+            }
+        }
+    }
+
+    fn entry(&mut self, entry: Node, path: &[String]) {
+        let mut fields: Vec<(String, String)> = Vec::new();
+        for string in entry.children().filter(|n| n.has_tag_name("String")) {
+            let value: String = child(string, "Value")
+                .map(|v| self.value(v))
+                .unwrap_or_default();
+            fields.push((child_text(string, "Key"), value));
+        }
+        let mut take = |name: &str| -> Option<String> {
+            let i: usize = fields.iter().position(|(k, _)| k == name)?;
+            Some(fields.remove(i).1).filter(|v| !v.is_empty())
+        };
+        let title: String = take("Title").unwrap_or_else(|| "untitled".to_owned());
+        let password: Option<String> = take("Password");
+        let username: Option<String> = take("UserName");
+        let url: Option<String> = take("URL");
+        let notes: Option<String> = take("Notes");
+
+        if child(entry, "History").is_some_and(|h| h.children().any(|n| n.is_element())) {
+            self.history += 1;
+        }
+        if child(entry, "Times").is_some_and(|t| child_text(t, "Expires") == "True") {
+            self.expiring += 1;
+        }
+        let Some(password) = password else {
+            self.no_password += 1;
+            return;
+        };
+
+        let mut meta: Vec<(String, String)> = Vec::new();
+        meta.extend(notes.map(|n| ("notes".to_owned(), n)));
+        meta.extend(
+            fields
+                .into_iter()
+                .filter(|(k, v)| !k.is_empty() && !v.is_empty()),
+        );
+        for binary in entry.children().filter(|n| n.has_tag_name("Binary")) {
+            let data: Option<&Vec<u8>> = child(binary, "Value")
+                .and_then(|v| v.attribute("Ref"))
+                .and_then(|r| r.parse::<usize>().ok())
+                .and_then(|r| self.binaries.get(r));
+            if let Some(data) = data {
+                let name: String = format!("attachment/{}", child_text(binary, "Key"));
+                meta.push((name, STANDARD.encode(data)));
+            }
+        }
+
+        let mut name: Vec<String> = path.to_vec();
+        name.push(title);
+        self.entries.push(Entry {
+            name: name.join("/"),
+            username,
+            password,
+            url,
+            meta,
+        });
+    }
+}
+
+/// Read the entries of the KDBX 4 database `bytes`, unlocking it with `password`.
+///
+/// Groups become the path in front of each key, below the top group.
+/// Notes, other fields, and attachments (in base64) are kept as metadata.
+/// Also returns a note for each thing in the database that is not imported.
+pub fn read(bytes: &[u8], password: &str) -> Result<(Vec<Entry>, Vec<String>)> {
+    let (xml, binaries, mut stream) = decrypt(bytes, password)?;
+    let doc: Document =
+        Document::parse(&xml).map_err(|e| ImportInvalid(format!("database XML: {}", e)))?;
+
+    // protected values are encrypted one after another in document order,
+    // so every one must be decrypted, even those that are not imported
+    let mut protected: HashMap<NodeId, String> = HashMap::new();
+    for node in doc.descendants() {
+        if node.has_tag_name("Value") && node.attribute("Protected") == Some("True") {
+            let mut value: Vec<u8> = STANDARD
+                .decode(node.text().unwrap_or_default())
+                .map_err(|_| invalid("database has a corrupted protected value"))?;
+            stream.apply(&mut value);
+            protected.insert(node.id(), String::from_utf8_lossy(&value).into_owned());
+        }
+    }
+
+    let root: Node = doc.root_element();
+    let meta: Option<Node> = child(root, "Meta");
+    let recycle_bin: Option<String> = meta
+        .filter(|m| child_text(*m, "RecycleBinEnabled") == "True")
+        .map(|m| child_text(m, "RecycleBinUUID"));
+    let Some(top) = child(root, "Root").and_then(|r| child(r, "Group")) else {
+        return Err(invalid("database has no groups"));
+    };
+
+    let mut walker: Walker = Walker {
+        protected,
+        binaries: &binaries,
+        recycle_bin,
+        entries: Vec::new(),
+        history: 0,
+        recycled: 0,
+        no_password: 0,
+        expiring: 0,
+    };
+    walker.group(top, &[]);
+
+    let mut notes: Vec<String> = Vec::new();
+    let counts: [(usize, &str); 4] = [
+        (
+            walker.history,
+            "entries have old versions in their history, which were not imported",
+        ),
+        (
+            walker.recycled,
+            "entries in the recycle bin were not imported",
+        ),
+        (
+            walker.no_password,
+            "entries without a password were not imported",
+        ),
+        (
+            walker.expiring,
+            "entries have expiry dates, which were not imported",
+        ),
+    ];
+    for (count, note) in counts {
+        if count > 0 {
+            notes.push(format!("{} {}", count, note));
+        }
+    }
+    Ok((walker.entries, notes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // made by testdata/mkkdbx.py, a separate implementation of the format
+    const ARGON2_CHACHA20: &[u8] = include_bytes!("../testdata/argon2id-chacha20.kdbx");
+    const AESKDF_AES256: &[u8] = include_bytes!("../testdata/aeskdf-aes256.kdbx");
+
+    fn check(bytes: &[u8]) {
+        let (entries, notes) = read(bytes, "password").unwrap();
+        assert_eq!(entries.len(), 2);
+
+        let github: &Entry = &entries[0];
+        assert_eq!(github.name, "github");
+        assert_eq!(github.password, "hunter2");
+        assert_eq!(github.username.as_deref(), Some("me"));
+        assert_eq!(github.url.as_deref(), Some("https://github.com"));
+        assert_eq!(
+            github.meta,
+            vec![
+                ("notes".to_owned(), "line one\nline two".to_owned()),
+                ("Recovery".to_owned(), "abcd-efgh".to_owned()),
+                (
+                    "attachment/codes.txt".to_owned(),
+                    STANDARD.encode("1234 5678\n")
+                ),
+            ]
+        );
+
+        assert_eq!(entries[1].name, "work/prod/db");
+        assert_eq!(entries[1].password, "s3cret <>");
+        assert_eq!(
+            notes,
+            vec![
+                "1 entries have old versions in their history, which were not imported",
+                "1 entries without a password were not imported"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read() {
+        check(ARGON2_CHACHA20);
+        check(AESKDF_AES256);
+    }
+
+    #[test]
+    fn test_read_invalid() {
+        assert!(read(ARGON2_CHACHA20, "wrong").is_err());
+        assert!(read(&ARGON2_CHACHA20[..200], "password").is_err());
+        assert!(read(b"not a database", "password").is_err());
+        let mut corrupted: Vec<u8> = AESKDF_AES256.to_vec();
+        let last: usize = corrupted.len() - 40;
+        corrupted[last] ^= 1;
+        assert!(read(&corrupted, "password").is_err());
+    }
+}
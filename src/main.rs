@@ -1,101 +1,244 @@
+mod args;
+mod audit;
 mod cfg;
+mod clipboard;
 mod crypt;
+mod dockercred;
 mod errors;
+mod export;
+mod gitcred;
+mod header;
+mod import;
+mod kdbx;
+mod merge;
+mod oplog;
+mod passstore;
+mod password;
+mod recipient;
+mod recovery;
+mod sshagent;
+mod sync;
+mod template;
 mod vault;
+mod verify;
 
-use arboard::Clipboard;
-use rpassword::read_password;
+use rpassword::prompt_password;
+use serde_json::json;
 use std::{
+    collections::BTreeMap,
     env,
-    io::{stdin, stdout, Write},
+    fs::{read, File, OpenOptions},
+    io::{stdin, stdout, IsTerminal, Read, Write},
     path::{Path, PathBuf},
-    thread::sleep,
+    process::{self, Command},
     time::Duration,
 };
 
 use crate::{
+    args::Args,
+    crypt::{MAX_COST, MIN_COST},
     errors::{
         Result,
-        SrpkError::{KeyReserved, NoParam, NoVault},
+        SrpkError::{
+            AgentFailed, AuditBroken, CostRange, FieldNonExist, FlagInvalid, KeyNonExist,
+            KeyReserved, MergeConflict, NoParam, NoVault, PathEmpty, PathTaken, RunFailed,
+            StdoutTerminal, SyncLogInvalid, TempStale, VerifyFailed,
+        },
     },
-    vault::Vault,
+    vault::{Unlock, Vault, SCHEMA_VERSION},
 };
 
-const RESERVED: [&str; 7] = ["help", "init", "use", "which", "mk", "rm", "ls"];
+/// Target time to unlock a vault if `--unlock-ms` is not given.
+const DEFAULT_UNLOCK_MS: u64 = 1000;
+
+/// Days removed keys stay in the trash if the `trash_retention_days` setting is unset.
+const DEFAULT_TRASH_DAYS: u64 = 30;
+
+const RESERVED: [&str; 29] = [
+    "help",
+    "get",
+    "init",
+    "use",
+    "which",
+    "mk",
+    "rm",
+    "trash",
+    "audit",
+    "ls",
+    "keygen",
+    "recipient",
+    "recovery",
+    "rekey",
+    "bench",
+    "verify",
+    "config",
+    "run",
+    "inject",
+    "git-credential",
+    "docker-credential",
+    "ssh",
+    "ssh-agent",
+    "import",
+    "export",
+    "merge",
+    "diff",
+    "sync",
+    clipboard::HELPER,
+];
+
+/// Programs srpk can be installed as, by linking to it under their name,
+/// and the srpk command each of them runs.
+const PROGRAMS: [(&str, &str); 2] = [
+    (gitcred::PROGRAM, "git-credential"),
+    (dockercred::PROGRAM, "docker-credential"),
+];
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    let action: &str = match args.get(1) {
+    let mut argv: Vec<String> = env::args().collect();
+    let program: String = argv.remove(0);
+    let program: Option<&str> = Path::new(&program).file_stem().and_then(|p| p.to_str());
+    if let Some((_, action)) = PROGRAMS.iter().find(|(name, _)| Some(*name) == program) {
+        argv.insert(0, action.to_string());
+    }
+    let args: Args = Args::parse(argv);
+    let action: &str = match args.get(0) {
         Some(action) => action,
         None => "help",
     };
-    let param: Option<&String> = args.get(2);
+    let param: Option<&String> = args.get(1);
+    let param2: Option<&String> = args.get(2);
 
     let out: Result<()> = match action {
         "help" => {
             help();
             Ok(())
         }
-        "init" => vault_init(&param),
+        "init" => vault_init(&param, &args),
         "use" => vault_use(&param),
         "which" => vault_which(),
-        "mk" => key_mk(&param),
-        "rm" => key_rm(&param),
-        "ls" => key_ls(),
-        _ => all(action),
+        "get" => match param {
+            Some(key) => key_get(key, &args),
+            None => Err(NoParam),
+        },
+        "mk" => key_mk(&param, &args),
+        "rm" => key_rm(&param, &args),
+        "ls" => key_ls(&args),
+        "trash" => match param.map(|s| s.as_str()) {
+            Some("ls") => trash_ls(&args),
+            Some("restore") => trash_restore(&param2, &args),
+            Some("empty") => trash_empty(&args),
+            _ => Err(NoParam),
+        },
+        "audit" => audit_ls(&args),
+        "keygen" => identity_keygen(&param),
+        "recipient" => match param.map(|s| s.as_str()) {
+            Some("add") => recipient_add(&param2, &args),
+            Some("rm") => recipient_rm(&param2, &args),
+            Some("ls") => recipient_ls(&args),
+            _ => Err(NoParam),
+        },
+        "recovery" => match param.map(|s| s.as_str()) {
+            Some("split") => recovery_split(&args),
+            Some("combine") => recovery_combine(&args),
+            _ => Err(NoParam),
+        },
+        "rekey" => vault_rekey(&args),
+        "bench" => bench(&args),
+        "verify" => vault_verify(&param, &args),
+        "config" => config(&param, &param2, &args),
+        "run" => run(&args),
+        "inject" => inject(&args),
+        "git-credential" => git_credential(&param, &args),
+        "docker-credential" => docker_credential(&param, &args),
+        "ssh" => match param.map(|s| s.as_str()) {
+            Some("import") => ssh_import(&param2, &args.get(3), &args),
+            _ => Err(NoParam),
+        },
+        "ssh-agent" => ssh_agent(&args),
+        "import" => import(&param, &args),
+        "export" => export(&args),
+        "merge" => vault_merge(&param, &args),
+        "diff" => vault_diff(&param, &param2, &args),
+        "sync" => match param.map(|s| s.as_str()) {
+            Some("init") => sync_init(&param2, &args),
+            None if args.flag("--dir") => sync_dir(&args),
+            None => sync(&args),
+            _ => Err(NoParam),
+        },
+        clipboard::HELPER => match (param.map(|t| t.parse::<u64>()), param2) {
+            (Some(Ok(timeout)), Some(backends)) => clipboard::parse_backends(Some(backends))
+                .and_then(|b| clipboard::helper(timeout, &b, args.get(3).map(Path::new))),
+            _ => Err(NoParam),
+        },
+        _ => all(action, &args),
     };
 
-    if out.is_err() {
-        println!("error: {}", out.unwrap_err());
+    if let Err(e) = out {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+    // sync commits for itself, and the clipboard helper runs detached long after its' command
+    if vault::written() && action != "sync" && action != clipboard::HELPER {
+        sync_commit(action, param);
     }
 }
 
-fn get_cost() -> Result<u8> {
-    let mut cost: String = String::new();
-    loop {
-        cost.clear();
-        print!("crypt slowness (5-31, higher = slower, 12-13 is good): ");
-        stdout().flush()?;
-        stdin().read_line(&mut cost)?.to_string();
-        let trim: &str = cost.trim();
-
-        match trim.parse::<u8>() {
-            Ok(u) => {
-                if !(5..=31).contains(&u) {
-                    println!("out of range");
-                    continue;
-                }
-                return Ok(u);
-            }
-            Err(_) => {
-                println!("not a valid number");
+/// Pick the bcrypt cost for a new password,
+/// either from `--cost` or by measuring this machine against `--unlock-ms`.
+fn get_cost(args: &Args) -> Result<u8> {
+    let cost: u8 = match args.value_parse::<u8>("--cost")? {
+        Some(cost) => {
+            if !(MIN_COST..=MAX_COST).contains(&cost) {
+                return Err(CostRange);
             }
+            cost
         }
-    }
+        None => {
+            let unlock_ms: u64 = args
+                .value_parse("--unlock-ms")?
+                .unwrap_or(DEFAULT_UNLOCK_MS);
+            println!("measuring crypt speed for a {}ms unlock...", unlock_ms);
+            crypt::bcrypt_calibrate(Duration::from_millis(unlock_ms))?
+        }
+    };
+
+    let estimate: Duration = crypt::bcrypt_time(MIN_COST)? * 2u32.pow((cost - MIN_COST) as u32);
+    println!(
+        "using crypt cost {}, unlocking will take about {}ms",
+        cost,
+        estimate.as_millis()
+    );
+    Ok(cost)
 }
 
+/// Prompt for a password on the terminal, keeping stdout free for output.
 fn get_password(prompt: &str) -> Result<String> {
-    print!("{}: ", prompt);
-    stdout().flush()?;
-    Ok(read_password()?)
+    Ok(prompt_password(format!("{}: ", prompt))?)
 }
 
-fn get_password_confirm(prompt: &str) -> Result<String> {
+/// Get the vault password from wherever `args` say, or prompt for it with `prompt`.
+fn get_vault_password(prompt: &str, args: &Args) -> Result<String> {
+    match password::read_from(args, &password::VAULT)? {
+        Some(pass) => Ok(pass),
+        None => get_password(prompt),
+    }
+}
+
+/// Get a new password from `source`, or prompt for it twice with `prompt`.
+fn get_password_confirm(prompt: &str, args: &Args, source: &password::Source) -> Result<String> {
+    if let Some(pass) = password::read_from(args, source)? {
+        return Ok(pass);
+    }
     loop {
         let pass: String = get_password(prompt)?;
         let pass_confirm: String = get_password("retype password")?;
         if pass == pass_confirm {
             return Ok(pass);
         }
-        println!("passwords do not match")
+        eprintln!("passwords do not match")
     }
 }
 
-pub fn set_clipboard(text: &str) -> Result<()> {
-    let mut clipboard = Clipboard::new()?;
-    Ok(clipboard.set_text(text)?)
-}
-
 fn param_check(param: &Option<&String>) -> Result<()> {
     if param.is_none() {
         return Err(NoParam);
@@ -110,20 +253,107 @@ fn vault_check() -> Result<String> {
     }
 }
 
-fn to_clipboard(pass: &str) -> Result<()> {
-    set_clipboard(pass)?;
-    println!("pass has been put into clipboard, and will be cleared in 10s");
-    let wait_duration: Duration = Duration::from_secs(10);
-    sleep(wait_duration);
-    set_clipboard("")?;
-    println!("clipboard cleared");
-    Ok(())
+/// Open the active vault, using the identity file in `SRPK_IDENTITY` if set,
+/// or prompting for the vault password otherwise.
+fn vault_open(args: &Args) -> Result<Vault> {
+    let path: String = vault_check()?;
+    vault_open_path(&path, "password for active vault", args)
+}
+
+/// Open the vault at `path`, like `vault_open`, prompting with `prompt` for a password.
+///
+/// Changes are logged as made by this device once `srpk sync --dir` has been used with the vault.
+fn vault_open_path(path: &str, prompt: &str, args: &Args) -> Result<Vault> {
+    let device: String = cfg::get_device_id()?;
+    let mut vault: Vault = vault_unlock(path, prompt, args, false)?;
+    vault.set_device(&device);
+    Ok(vault)
 }
 
-fn all(param: &str) -> Result<()> {
+/// Unlock the vault at `path` with `SRPK_IDENTITY` or a password,
+/// opening it without migrating its' schema if `raw`.
+fn vault_unlock(path: &str, prompt: &str, args: &Args, raw: bool) -> Result<Vault> {
+    let identity: Option<[u8; 32]> = match env::var_os("SRPK_IDENTITY") {
+        Some(identity) => Some(recipient::read_identity(Path::new(&identity))?),
+        None => None,
+    };
+    let pass: String = match identity {
+        Some(_) => String::new(),
+        None => get_vault_password(prompt, args)?,
+    };
+    let with: Unlock = match &identity {
+        Some(identity) => Unlock::Identity(identity),
+        None => Unlock::Password(&pass),
+    };
+
+    temp_check(path, with)?;
+    match &identity {
+        _ if raw => Vault::open_raw(path, with),
+        Some(identity) => Vault::open_identity(path, identity),
+        None => Vault::open(path, &pass),
+    }
+}
+
+/// Deal with an unencrypted temporary database left behind
+/// by an interrupted session with the vault at `path`, if there is one.
+///
+/// Returns `Err(TempStale)` if the user chooses to leave it alone.
+fn temp_check(path: &str, with: Unlock) -> Result<()> {
+    let Some(temp) = Vault::temp_stale(path) else {
+        return Ok(());
+    };
+
+    eprintln!(
+        "WARNING: {} is an unencrypted copy of your vault left behind by an interrupted srpk session!",
+        temp.display()
+    );
+    eprintln!("it may hold changes that were never saved, and should not stay on disk");
+    loop {
+        eprint!("[m]erge its' changes into the vault, [s]hred it, or [a]bort: ");
+        let mut choice: String = String::new();
+        if stdin().read_line(&mut choice)? == 0 {
+            return Err(TempStale(temp));
+        }
+        match choice.trim() {
+            "m" => match Vault::temp_merge(path, with) {
+                Ok(()) => {
+                    eprintln!(
+                        "merged changes into {} and shredded {}",
+                        path,
+                        temp.display()
+                    );
+                    return Ok(());
+                }
+                Err(e) => eprintln!("could not merge: {}", e),
+            },
+            "s" => {
+                Vault::temp_shred(path)?;
+                eprintln!("shredded {}", temp.display());
+                return Ok(());
+            }
+            "a" => return Err(TempStale(temp)),
+            _ => continue,
+        }
+    }
+}
+
+/// Get how long secrets stay in the clipboard,
+/// from `--timeout`, the `clipboard_timeout` setting, or the default.
+fn clipboard_timeout(args: &Args) -> Result<u64> {
+    let timeout: u64 = match args.value_parse("--timeout")? {
+        Some(timeout) => timeout,
+        None => cfg::get_setting_parse("clipboard_timeout")?.unwrap_or(clipboard::DEFAULT_TIMEOUT),
+    };
+    if timeout == 0 {
+        return Err(FlagInvalid("--timeout".to_owned()));
+    }
+    Ok(timeout)
+}
+
+fn all(param: &str, args: &Args) -> Result<()> {
     let vault: Option<PathBuf> = cfg::get_active_vault()?;
     match vault {
-        Some(_) => key_get(param),
+        Some(_) => key_get(param, args),
         None => {
             help();
             Ok(())
@@ -131,14 +361,14 @@ fn all(param: &str) -> Result<()> {
     }
 }
 
-fn vault_init(param: &Option<&String>) -> Result<()> {
+fn vault_init(param: &Option<&String>, args: &Args) -> Result<()> {
     param_check(param)?;
     let mut path: String = param.unwrap().to_owned();
     if !path.ends_with(".db") {
         path.push_str(".db");
     }
-    let pass: String = get_password_confirm("password for the new vault")?;
-    let cost: u8 = get_cost()?;
+    let cost: u8 = get_cost(args)?;
+    let pass: String = get_password_confirm("password for the new vault", args, &password::VAULT)?;
     Vault::create(&path, &pass, cost)?;
     println!("successfully created new vault at {}", path);
 
@@ -171,7 +401,7 @@ fn vault_which() -> Result<()> {
     }
 }
 
-fn key_mk(param: &Option<&String>) -> Result<()> {
+fn key_mk(param: &Option<&String>, args: &Args) -> Result<()> {
     param_check(param)?;
     let key: &str = param.unwrap();
 
@@ -179,43 +409,115 @@ fn key_mk(param: &Option<&String>) -> Result<()> {
         return Err(KeyReserved(key.to_string()));
     }
 
-    let path: String = vault_check()?;
-    let pass: String = get_password("password for active vault")?;
-    let vault: Vault = Vault::open(&path, &pass)?;
+    let vault: Vault = vault_open(args)?;
 
-    let new_pass: String = get_password("new password to add")?;
+    let new_pass: String = match password::read_from(args, &password::NEW)? {
+        Some(pass) => pass,
+        None => get_password("new password to add")?,
+    };
     vault.key_new(key, &new_pass)?;
+    if let Some(username) = args.value("--username") {
+        vault.meta_set(key, "username", Some(username))?;
+    }
     vault.close(true)?;
 
     println!("successfully added new key {}", key);
     Ok(())
 }
 
-fn key_rm(param: &Option<&String>) -> Result<()> {
+fn key_rm(param: &Option<&String>, args: &Args) -> Result<()> {
     param_check(param)?;
     let key: &str = param.unwrap();
 
-    let path: String = vault_check()?;
-    let pass: String = get_password("password for active vault")?;
-    let vault: Vault = Vault::open(&path, &pass)?;
+    let vault: Vault = vault_open(args)?;
 
-    vault.key_del(key)?;
-    vault.close(true)?;
+    let removed: Result<usize> = vault.transaction(|v| {
+        v.key_del(key)?;
+        trash_expire(v)
+    });
+    vault.close(removed.is_ok())?;
+    removed?;
 
-    println!("successfully removed key {}", key);
+    println!(
+        "moved key {} to the trash; srpk trash restore {} to bring it back",
+        key, key
+    );
     Ok(())
 }
 
-fn key_get(key: &str) -> Result<()> {
-    let path: String = vault_check()?;
-    let pass: String = get_password("password for active vault")?;
-    let vault: Vault = Vault::open(&path, &pass)?;
+/// Permanently delete the keys that have been in the trash of `vault`
+/// for longer than the `trash_retention_days` setting.
+///
+/// Returns how many keys were deleted.
+fn trash_expire(vault: &Vault) -> Result<usize> {
+    let days: u64 = cfg::get_setting_parse("trash_retention_days")?.unwrap_or(DEFAULT_TRASH_DAYS);
+    vault.trash_purge(vault::now().saturating_sub(days.saturating_mul(86400)))
+}
 
-    let found: Option<String> = vault.key_get(key)?;
-    vault.close(false)?;
+fn trash_ls(args: &Args) -> Result<()> {
+    let vault: Vault = vault_open(args)?;
+
+    let listed: Result<(usize, Vec<(String, u64)>)> =
+        vault.transaction(|v| Ok((trash_expire(v)?, v.trash_ls()?)));
+    let expired: bool = matches!(listed, Ok((n, _)) if n > 0);
+    vault.close(expired)?;
+    let (_, keys) = listed?;
+
+    if keys.is_empty() {
+        println!("trash is empty");
+    }
+    let now: u64 = vault::now();
+    for (key, deleted) in keys {
+        println!("{}  (removed {})", key, merge::ago(deleted, now));
+    }
+    Ok(())
+}
+
+fn trash_restore(param: &Option<&String>, args: &Args) -> Result<()> {
+    param_check(param)?;
+    let key: &str = param.unwrap();
+
+    let vault: Vault = vault_open(args)?;
+
+    let restored: Result<()> = vault.transaction(|v| {
+        trash_expire(v)?;
+        v.trash_restore(key)
+    });
+    vault.close(restored.is_ok())?;
+    restored?;
+
+    println!("restored key {}", key);
+    Ok(())
+}
+
+fn trash_empty(args: &Args) -> Result<()> {
+    let vault: Vault = vault_open(args)?;
+
+    let emptied: Result<usize> = vault.trash_purge(u64::MAX);
+    vault.close(matches!(emptied, Ok(n) if n > 0))?;
+
+    println!("permanently deleted {} key(s) from the trash", emptied?);
+    Ok(())
+}
 
-    match found {
-        Some(p) => to_clipboard(&p),
+fn key_get(key: &str, args: &Args) -> Result<()> {
+    if args.flag("--stdout") || args.flag("--json") {
+        return key_print(key, args);
+    }
+
+    let timeout: u64 = clipboard_timeout(args)?;
+    let backends: Vec<clipboard::Backend> =
+        clipboard::parse_backends(cfg::get_setting("clipboard_backend")?.as_deref())?;
+    let reads: bool = audit_reads()?;
+    let vault: Vault = vault_open(args)?;
+
+    let found: Result<Option<String>> = vault
+        .key_get(key)
+        .and_then(|found| audit_get(&vault, key, found, reads));
+    vault.close(reads && matches!(found, Ok(Some(_))))?;
+
+    match found? {
+        Some(p) => clipboard::copy(&p, timeout, &backends, args.flag("--wait")),
         None => {
             println!("key {} not found", key);
             Ok(())
@@ -223,10 +525,105 @@ fn key_get(key: &str) -> Result<()> {
     }
 }
 
-fn key_ls() -> Result<()> {
-    let path: String = vault_check()?;
-    let pass: String = get_password("password for active vault")?;
-    let vault: Vault = Vault::open(&path, &pass)?;
+/// Print password `key` to stdout for scripts, alone or with its' username as JSON.
+///
+/// Returns `Err(StdoutTerminal)` if stdout is a terminal, unless `--force` is passed.
+fn key_print(key: &str, args: &Args) -> Result<()> {
+    if stdout().is_terminal() && !args.flag("--force") {
+        return Err(StdoutTerminal);
+    }
+    let reads: bool = audit_reads()?;
+    let vault: Vault = vault_open(args)?;
+
+    let found: Result<(Option<String>, Option<String>)> = vault
+        .key_get(key)
+        .and_then(|found| audit_get(&vault, key, found, reads))
+        .and_then(|found| Ok((found, vault.meta_get(key, "username")?)));
+    vault.close(reads && matches!(found, Ok((Some(_), _))))?;
+    let (found, username) = found?;
+
+    let Some(value) = found else {
+        return Err(KeyNonExist(key.to_owned()));
+    };
+    let output: String = match args.flag("--json") {
+        true => json!({ "key": key, "username": username, "value": value }).to_string(),
+        false => value,
+    };
+    let mut stdout = stdout();
+    match args.flag("--no-newline") {
+        true => write!(stdout, "{}", output)?,
+        false => writeln!(stdout, "{}", output)?,
+    }
+    Ok(stdout.flush()?)
+}
+
+/// Whether reading a password is recorded in the audit log, from the `audit_reads` setting.
+///
+/// On by default; turning it off keeps reads from rewriting the vault, but leaves no trace of them.
+fn audit_reads() -> Result<bool> {
+    Ok(cfg::get_setting_parse("audit_reads")?.unwrap_or(true))
+}
+
+/// Record in the audit log of `vault` that password `key` was read, if it was `found` and `reads` is set.
+fn audit_get(
+    vault: &Vault,
+    key: &str,
+    found: Option<String>,
+    reads: bool,
+) -> Result<Option<String>> {
+    if reads && found.is_some() {
+        vault.audit("get", Some(key))?;
+    }
+    Ok(found)
+}
+
+/// Show the audit log of the active vault, checking that each event chains from the one before
+/// and that none were removed from the end.
+///
+/// Returns `Err(AuditBroken)` if the chain is broken, after showing the log.
+fn audit_ls(args: &Args) -> Result<()> {
+    let vault: Vault = vault_open(args)?;
+
+    let events: Result<(Vec<audit::Event>, Option<usize>)> = vault
+        .audit_ls()
+        .and_then(|events| Ok((events, vault.audit_verify()?)));
+    vault.close(false)?;
+    let (events, broken) = events?;
+
+    for (i, event) in events.iter().enumerate() {
+        if broken == Some(i) {
+            println!("-- events from here on do not match those before --");
+        }
+        println!(
+            "{}  {}@{}  {}{}",
+            audit::timestamp(event.time),
+            event.user,
+            event.host,
+            event.action,
+            event
+                .key
+                .as_ref()
+                .map_or(String::new(), |k| format!(" {}", k))
+        );
+    }
+    if broken == Some(events.len()) {
+        println!("-- events after these were removed --");
+    }
+    match broken {
+        Some(i) => Err(AuditBroken(i + 1)),
+        None if events.is_empty() => {
+            println!("audit log is empty");
+            Ok(())
+        }
+        None => {
+            println!("{} event(s), none changed or removed", events.len());
+            Ok(())
+        }
+    }
+}
+
+fn key_ls(args: &Args) -> Result<()> {
+    let vault: Vault = vault_open(args)?;
 
     let keys: Vec<String> = vault.key_ls()?;
     vault.close(false)?;
@@ -240,22 +637,1333 @@ fn key_ls() -> Result<()> {
     Ok(())
 }
 
+fn identity_keygen(param: &Option<&String>) -> Result<()> {
+    param_check(param)?;
+    let path: &Path = Path::new(param.unwrap());
+    let (secret, public) = crypt::x25519_generate();
+    recipient::write_identity(path, &secret, &public)?;
+    println!("wrote identity to {}", path.to_str().unwrap());
+    println!("public key: {}", recipient::encode_public(&public));
+    Ok(())
+}
+
+fn recipient_add(param: &Option<&String>, args: &Args) -> Result<()> {
+    param_check(param)?;
+    let public: [u8; 32] = recipient::decode_public(param.unwrap())?;
+
+    let mut vault: Vault = vault_open(args)?;
+    vault.recipient_add(&public)?;
+    vault.close(true)?;
+
+    println!("successfully added recipient {}", param.unwrap());
+    Ok(())
+}
+
+fn recipient_rm(param: &Option<&String>, args: &Args) -> Result<()> {
+    param_check(param)?;
+    let public: [u8; 32] = recipient::decode_public(param.unwrap())?;
+
+    let mut vault: Vault = vault_open(args)?;
+    vault.recipient_del(&public)?;
+    vault.close(true)?;
+
+    println!("successfully removed recipient {}", param.unwrap());
+    Ok(())
+}
+
+fn recipient_ls(args: &Args) -> Result<()> {
+    let vault: Vault = vault_open(args)?;
+    let recipients: Vec<[u8; 32]> = vault.recipient_ls();
+    vault.close(false)?;
+
+    if recipients.is_empty() {
+        println!("vault has no recipients");
+    } else {
+        for public in recipients {
+            println!("{}", recipient::encode_public(&public));
+        }
+    }
+
+    Ok(())
+}
+
+fn vault_verify(param: &Option<&String>, args: &Args) -> Result<()> {
+    let path: String = match param {
+        Some(p) => p.to_string(),
+        None => vault_check()?,
+    };
+
+    let bytes: Vec<u8> = read(&path)?;
+    match verify::check_header(&bytes) {
+        Ok(summary) => println!("header: ok, {}", summary),
+        Err(e) => {
+            println!("header: {}", e);
+            return Err(VerifyFailed(1));
+        }
+    }
+
+    // check the database as it was written, before opening it for use would migrate it
+    let vault: Vault = vault_unlock(&path, "password for vault", args, true)?;
+    println!("authentication: ok");
+    let checked: Result<usize> = (|| {
+        let mut problems: usize = 0;
+        let integrity: Vec<String> = vault.integrity_check()?;
+        if integrity.is_empty() {
+            println!("database integrity: ok");
+        } else {
+            for problem in &integrity {
+                println!("database integrity: {}", problem);
+            }
+            problems += integrity.len();
+        }
+
+        let version: usize = vault.schema_version()?;
+        if version > SCHEMA_VERSION {
+            println!(
+                "schema version: {} is newer than this srpk supports ({})",
+                version, SCHEMA_VERSION
+            );
+            return Ok(problems + 1);
+        } else if version < SCHEMA_VERSION {
+            println!(
+                "schema version: ok, {} of {}, upgraded when next opened",
+                version, SCHEMA_VERSION
+            );
+        } else {
+            println!("schema version: ok, {} of {}", version, SCHEMA_VERSION);
+        }
+
+        let duplicates: Vec<String> = vault.key_duplicates()?;
+        if duplicates.is_empty() {
+            println!("duplicate keys: none");
+        } else {
+            println!("duplicate keys: {}", duplicates.join(", "));
+            problems += duplicates.len();
+        }
+        Ok(problems)
+    })();
+    vault.close(false)?;
+    let problems: usize = checked?;
+
+    if problems > 0 {
+        return Err(VerifyFailed(problems));
+    }
+    println!("vault is healthy");
+    Ok(())
+}
+
+fn vault_rekey(args: &Args) -> Result<()> {
+    let mut vault: Vault = vault_open(args)?;
+    let pass: String = get_password_confirm("new password for the vault", args, &password::NEW)?;
+    let cost: u8 = match vault.cost() {
+        Some(cost) if !args.flag("--cost") && !args.flag("--unlock-ms") => cost,
+        _ => get_cost(args)?,
+    };
+    vault.rekey(&pass, cost)?;
+    vault.close(true)?;
+
+    println!("successfully rekeyed vault; any recovery shares are now invalid");
+    Ok(())
+}
+
+fn recovery_split(args: &Args) -> Result<()> {
+    let threshold: u8 = args.value_parse("--threshold")?.ok_or(NoParam)?;
+    let shares: u8 = args.value_parse("--shares")?.ok_or(NoParam)?;
+
+    let vault: Vault = vault_open(args)?;
+    let printed: Result<Vec<String>> = recovery::split(&vault.data_key(), threshold, shares)
+        .and_then(|printed| {
+            vault.audit("recovery split", None)?;
+            Ok(printed)
+        });
+    vault.close(printed.is_ok())?;
+    let printed: Vec<String> = printed?;
+
+    println!(
+        "any {} of these {} shares can recover the vault; store each one separately:\n",
+        threshold, shares
+    );
+    for (i, share) in printed.iter().enumerate() {
+        println!("share {} of {}:\n{}\n", i + 1, shares, share);
+    }
+    println!("shares stop working if the vault is rekeyed");
+    Ok(())
+}
+
+fn recovery_combine(args: &Args) -> Result<()> {
+    let path: String = vault_check()?;
+    let mut combiner: recovery::Combiner = recovery::Combiner::default();
+    loop {
+        match combiner.remaining() {
+            Some(0) => break,
+            Some(n) => print!("share ({} more needed): ", n),
+            None => print!("share: "),
+        }
+        stdout().flush()?;
+        let mut line: String = String::new();
+        if stdin().read_line(&mut line)? == 0 {
+            return Err(NoParam);
+        }
+        if let Err(e) = combiner.add(&line) {
+            println!("{}", e);
+        }
+    }
+
+    let key: [u8; 32] = combiner.combine()?;
+    temp_check(&path, Unlock::Key(&key))?;
+    let mut vault: Vault = Vault::open_key(&path, &key)?;
+    println!("shares accepted, the vault will now be rekeyed");
+    let pass: String = get_password_confirm("new password for the vault", args, &password::NEW)?;
+    let cost: u8 = match vault.cost() {
+        Some(cost) if !args.flag("--cost") && !args.flag("--unlock-ms") => cost,
+        _ => get_cost(args)?,
+    };
+    vault.rekey(&pass, cost)?;
+    vault.close(true)?;
+
+    println!("successfully recovered and rekeyed vault; old shares are now invalid");
+    Ok(())
+}
+
+fn bench(args: &Args) -> Result<()> {
+    let unlock_ms: u64 = args
+        .value_parse("--unlock-ms")?
+        .unwrap_or(DEFAULT_UNLOCK_MS);
+    let target: Duration = Duration::from_millis(unlock_ms);
+
+    println!("cost  unlock time");
+    let mut best: u8 = MIN_COST;
+    for cost in MIN_COST..=MAX_COST {
+        let time: Duration = crypt::bcrypt_time(cost)?;
+        println!("{:>4}  {}ms", cost, time.as_millis());
+        if time > target {
+            break;
+        }
+        best = cost;
+    }
+
+    println!("cost {} best fits a {}ms unlock", best, unlock_ms);
+    Ok(())
+}
+
+fn config(name: &Option<&String>, value: &Option<&String>, args: &Args) -> Result<()> {
+    let Some(name) = name else {
+        let settings: Vec<(String, String)> = cfg::get_settings()?;
+        if settings.is_empty() {
+            println!("all settings are default");
+        }
+        for (name, value) in settings {
+            println!("{} = {}", name, value);
+        }
+        return Ok(());
+    };
+
+    if args.flag("--unset") {
+        cfg::set_setting(name, None)?;
+        println!("{} is now default", name);
+        return Ok(());
+    }
+    match value {
+        Some(value) => {
+            cfg::set_setting(name, Some(value))?;
+            println!("{} = {}", name, value);
+        }
+        None => match cfg::get_setting(name)? {
+            Some(value) => println!("{} = {}", name, value),
+            None => println!("{} is default", name),
+        },
+    }
+    Ok(())
+}
+
+/// Run the command after `--` with passwords from the vault in its' environment,
+/// as given by each `--env NAME=key`.
+///
+/// On unix srpk is replaced by the command, so its' exit code is passed through.
+fn run(args: &Args) -> Result<()> {
+    let Some((program, program_args)) = args.trailing().split_first() else {
+        return Err(NoParam);
+    };
+    let mut mappings: Vec<(&str, &str)> = Vec::new();
+    for mapping in args.values("--env") {
+        match mapping.split_once('=') {
+            Some((name, key)) if !name.is_empty() && !key.is_empty() => mappings.push((name, key)),
+            _ => return Err(FlagInvalid("--env".to_owned())),
+        }
+    }
+
+    let reads: bool = audit_reads()?;
+    let vault: Vault = vault_open(args)?;
+    let found: Result<Vec<Option<String>>> = mappings
+        .iter()
+        .map(|(_, key)| {
+            vault
+                .key_get(key)
+                .and_then(|found| audit_get(&vault, key, found, reads))
+        })
+        .collect();
+    vault.close(reads && found.is_ok())?;
+
+    let mut command: Command = Command::new(program);
+    command.args(program_args);
+    for ((name, key), value) in mappings.iter().zip(found?) {
+        match value {
+            Some(value) => command.env(name, value),
+            None => return Err(KeyNonExist(key.to_string())),
+        };
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+
+        let e = command.exec();
+        Err(RunFailed(program.to_owned(), e.to_string()))
+    }
+    #[cfg(not(unix))]
+    {
+        let status = command
+            .status()
+            .map_err(|e| RunFailed(program.to_owned(), e.to_string()))?;
+        process::exit(status.code().unwrap_or(1));
+    }
+}
+
+/// Fill in the `{{ srpk "key" }}` and `{{ srpk "key" "field" }}` references
+/// in the template at `-i` with values from the vault, writing the result to `-o`.
+///
+/// The output is only readable by its' owner, and is not written at all if any reference is missing.
+fn inject(args: &Args) -> Result<()> {
+    let input: &String = args.value("-i").ok_or(NoParam)?;
+    let output: Option<&String> = args.value("-o");
+    if output.is_none() && stdout().is_terminal() && !args.flag("--force") {
+        return Err(StdoutTerminal);
+    }
+    let template: String = String::from_utf8(read(input)?)?;
+
+    let reads: bool = audit_reads()?;
+    let vault: Vault = vault_open(args)?;
+    let rendered: Result<String> = template::render(&template, |reference| {
+        let key: &str = &reference.key;
+        let value: Option<String> = match reference.field.as_deref() {
+            None | Some("password") => vault.key_get(key)?,
+            Some(field) => {
+                if vault.key_get(key)?.is_none() {
+                    return Err(KeyNonExist(key.to_owned()));
+                }
+                vault.meta_get(key, field)?
+            }
+        };
+        let value: Option<String> = audit_get(&vault, key, value, reads)?;
+        value.ok_or_else(|| match &reference.field {
+            Some(field) if field != "password" => FieldNonExist(key.to_owned(), field.to_owned()),
+            _ => KeyNonExist(key.to_owned()),
+        })
+    });
+    vault.close(reads && rendered.is_ok())?;
+    let rendered: String = rendered?;
+
+    match output {
+        Some(path) => {
+            write_private(Path::new(path), rendered.as_bytes())?;
+            eprintln!("wrote {}", path);
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+/// Write `contents` to `path`, making sure only the owner can read it.
+fn write_private(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut options: OpenOptions = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::fs::{set_permissions, Permissions};
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+        options.mode(0o600);
+        // an existing file keeps its' permissions when opened, so tighten them first
+        if path.exists() {
+            set_permissions(path, Permissions::from_mode(0o600))?;
+        }
+    }
+    let mut file: File = options.open(path)?;
+    file.write_all(contents)?;
+    Ok(file.sync_all()?)
+}
+
+/// Act as a git credential helper, answering `get`, `store` and `erase` from git on stdin.
+///
+/// Credentials are kept as `git/<host>[/<path>]` with their username.
+/// Unknown actions and requests without a host are ignored, as git expects.
+fn git_credential(action: &Option<&String>, args: &Args) -> Result<()> {
+    let request: gitcred::Credential = gitcred::Credential::read(stdin().lock())?;
+    let Some(key) = request.key() else {
+        return Ok(());
+    };
+
+    match action.map(|a| a.as_str()) {
+        Some("get") => {
+            let reads: bool = audit_reads()?;
+            let vault: Vault = vault_open(args)?;
+            let username: Option<String> = vault.meta_get(&key, "username")?;
+            // a different user on the same host is a different credential
+            let other_user: bool =
+                request.username.is_some() && username.is_some() && request.username != username;
+            let password: Option<String> = match other_user {
+                true => None,
+                false => audit_get(&vault, &key, vault.key_get(&key)?, reads)?,
+            };
+            vault.close(reads && password.is_some())?;
+
+            let Some(password) = password else {
+                return Ok(());
+            };
+            let found: gitcred::Credential = gitcred::Credential {
+                username: username.or(request.username),
+                password: Some(password),
+                ..Default::default()
+            };
+            found.write(stdout())
+        }
+        Some("store") => {
+            let (Some(username), Some(password)) = (&request.username, &request.password) else {
+                return Ok(());
+            };
+            let vault: Vault = vault_open(args)?;
+            let stored: Option<String> = vault.key_get(&key)?;
+            let changed: bool = stored.as_ref() != Some(password)
+                || vault.meta_get(&key, "username")?.as_ref() != Some(username);
+            if changed {
+                if stored.is_some() {
+                    vault.key_del(&key)?;
+                }
+                vault.key_new(&key, password)?;
+                vault.meta_set(&key, "username", Some(username))?;
+            }
+            vault.close(changed)
+        }
+        Some("erase") => {
+            let vault: Vault = vault_open(args)?;
+            let stored: Option<String> = vault.key_get(&key)?;
+            let erase: bool = match (&stored, &request.password) {
+                (Some(stored), Some(password)) => stored == password,
+                (Some(_), None) => true,
+                (None, _) => false,
+            };
+            if erase {
+                vault.key_del(&key)?;
+            }
+            vault.close(erase)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Act as a docker credential helper for `get`, `store`, `erase` and `list`.
+///
+/// Credentials are kept as `docker/<server>` with their username.
+/// Like other helpers, a missing credential is reported to docker on stdout.
+fn docker_credential(action: &Option<&String>, args: &Args) -> Result<()> {
+    let action: &str = action.map(|a| a.as_str()).ok_or(NoParam)?;
+    let mut input: String = String::new();
+    if action != "list" {
+        stdin().read_to_string(&mut input)?;
+    }
+
+    match action {
+        "get" => {
+            let server: String = dockercred::read_server(&input)?;
+            let key: String = dockercred::key(&server);
+            let reads: bool = audit_reads()?;
+            let vault: Vault = vault_open(args)?;
+            let secret: Option<String> = audit_get(&vault, &key, vault.key_get(&key)?, reads)?;
+            let username: Option<String> = vault.meta_get(&key, "username")?;
+            vault.close(reads && secret.is_some())?;
+
+            let Some(secret) = secret else {
+                println!("{}", dockercred::NOT_FOUND);
+                process::exit(1);
+            };
+            let found: dockercred::Credential = dockercred::Credential {
+                server,
+                username: username.unwrap_or_default(),
+                secret,
+            };
+            println!("{}", found.to_json());
+            Ok(())
+        }
+        "store" => {
+            let credential: dockercred::Credential = dockercred::Credential::from_json(&input)?;
+            let key: String = dockercred::key(&credential.server);
+            let vault: Vault = vault_open(args)?;
+            if vault.key_get(&key)?.is_some() {
+                vault.key_del(&key)?;
+            }
+            vault.key_new(&key, &credential.secret)?;
+            vault.meta_set(&key, "username", Some(&credential.username))?;
+            vault.close(true)
+        }
+        "erase" => {
+            let key: String = dockercred::key(&dockercred::read_server(&input)?);
+            let vault: Vault = vault_open(args)?;
+            if vault.key_get(&key)?.is_none() {
+                vault.close(false)?;
+                println!("{}", dockercred::NOT_FOUND);
+                process::exit(1);
+            }
+            vault.key_del(&key)?;
+            vault.close(true)
+        }
+        "list" => {
+            let reads: bool = audit_reads()?;
+            let vault: Vault = vault_open(args)?;
+            let mut servers: serde_json::Map<String, serde_json::Value> = serde_json::Map::new();
+            for key in vault.key_ls()? {
+                if let Some(server) = dockercred::server(&key) {
+                    let username: Option<String> = vault.meta_get(&key, "username")?;
+                    servers.insert(server.to_owned(), username.unwrap_or_default().into());
+                }
+            }
+            if reads {
+                vault.audit("list", None)?;
+            }
+            vault.close(reads)?;
+            println!("{}", serde_json::Value::Object(servers));
+            Ok(())
+        }
+        _ => Err(NoParam),
+    }
+}
+
+/// Store the OpenSSH private key at `file` as `ssh/<name>`,
+/// named after the file if no name is given.
+///
+/// Encrypted keys are decrypted first, as the vault already encrypts them.
+fn ssh_import(file: &Option<&String>, name: &Option<&String>, args: &Args) -> Result<()> {
+    param_check(file)?;
+    let path: &Path = Path::new(file.unwrap());
+    let name: String = match name {
+        Some(name) => name.to_string(),
+        None => path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .ok_or(NoParam)?,
+    };
+    let key: String = format!("{}{}", sshagent::KEY_PREFIX, name);
+    let lifetime: Option<u64> = args.value_parse("--lifetime")?;
+
+    let pem: String = String::from_utf8(read(path)?)?;
+    let pem: String = sshagent::import(&pem, || {
+        get_password(&format!("passphrase for {}", path.display()))
+    })?;
+
+    let vault: Vault = vault_open(args)?;
+    let added: Result<()> = vault.key_new(&key, &pem).and_then(|_| {
+        if args.flag("--confirm") {
+            vault.meta_set(&key, "confirm", Some("yes"))?;
+        }
+        if let Some(lifetime) = lifetime {
+            vault.meta_set(&key, "lifetime", Some(&lifetime.to_string()))?;
+        }
+        Ok(())
+    });
+    vault.close(added.is_ok())?;
+    added?;
+
+    println!("successfully added ssh key {}", key);
+    Ok(())
+}
+
+/// Serve the `ssh/` keys in the vault to ssh clients over a unix socket,
+/// printing the `SSH_AUTH_SOCK` to use it with, until stopped.
+///
+/// It stays in the foreground so `--confirm` can ask on its' terminal.
+///
+/// `--confirm` and `--lifetime` apply to every key,
+/// on top of the ones given to each key when it was imported.
+#[cfg(unix)]
+fn ssh_agent(args: &Args) -> Result<()> {
+    use std::os::unix::{
+        fs::{DirBuilderExt, PermissionsExt},
+        net::{UnixListener, UnixStream},
+    };
+
+    let lifetime: Option<u64> = args.value_parse("--lifetime")?;
+    let reads: bool = audit_reads()?;
+    let vault: Vault = vault_open(args)?;
+    let mut keys: Vec<sshagent::AgentKey> = Vec::new();
+    let loaded: Result<()> = vault.key_ls().and_then(|names| {
+        for key in names {
+            let Some(name) = key.strip_prefix(sshagent::KEY_PREFIX) else {
+                continue;
+            };
+            let pem: String = vault.key_get(&key)?.ok_or(KeyNonExist(key.clone()))?;
+            let confirm: bool = args.flag("--confirm")
+                || vault.meta_get(&key, "confirm")?.as_deref() == Some("yes");
+            let key_lifetime: Option<u64> = vault
+                .meta_get(&key, "lifetime")?
+                .and_then(|l| l.parse().ok());
+            let lifetime: Option<u64> = match (key_lifetime, lifetime) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            keys.push(sshagent::AgentKey::new(
+                name,
+                &pem,
+                confirm,
+                lifetime.map(Duration::from_secs),
+            )?);
+            if reads {
+                vault.audit("agent load", Some(&key))?;
+            }
+        }
+        Ok(())
+    });
+    vault.close(reads && loaded.is_ok() && !keys.is_empty())?;
+    loaded?;
+    if keys.is_empty() {
+        return Err(AgentFailed(
+            "vault has no ssh keys, add one with srpk ssh import".to_owned(),
+        ));
+    }
+
+    let socket: PathBuf = match (args.value("--socket"), env::var_os("XDG_RUNTIME_DIR")) {
+        (Some(socket), _) => PathBuf::from(socket),
+        (None, Some(runtime)) => Path::new(&runtime).join("srpk-agent.sock"),
+        (None, None) => {
+            // like ssh-agent, keep the socket in a directory only we can enter
+            let dir: PathBuf = env::temp_dir().join(format!("srpk-agent-{}", process::id()));
+            std::fs::DirBuilder::new().mode(0o700).create(&dir)?;
+            dir.join("agent.sock")
+        }
+    };
+    if socket.exists() {
+        if UnixStream::connect(&socket).is_ok() {
+            return Err(AgentFailed(format!(
+                "an agent is already running at {}",
+                socket.display()
+            )));
+        }
+        std::fs::remove_file(&socket)?;
+    }
+    let listener: UnixListener = UnixListener::bind(&socket)?;
+    std::fs::set_permissions(&socket, std::fs::Permissions::from_mode(0o600))?;
+
+    println!("SSH_AUTH_SOCK={}; export SSH_AUTH_SOCK;", socket.display());
+    stdout().flush()?;
+    eprintln!(
+        "serving {} ssh key(s) from the vault, press Ctrl-C to stop",
+        keys.len()
+    );
+    let agent: sshagent::Agent = sshagent::Agent::new(keys, Box::new(sshagent::confirm_tty));
+    sshagent::serve(listener, agent)
+}
+
+#[cfg(not(unix))]
+fn ssh_agent(_args: &Args) -> Result<()> {
+    Err(AgentFailed(
+        "only supported on systems with unix sockets".to_owned(),
+    ))
+}
+
+/// Add the logins in the CSV export, KeePass database or password store `file` to the vault,
+/// all at once so that a failure leaves the vault as it was.
+fn import(file: &Option<&String>, args: &Args) -> Result<()> {
+    param_check(file)?;
+    let format: import::Format = args
+        .value("--format")
+        .ok_or(NoParam)?
+        .parse()
+        .map_err(|_| FlagInvalid("--format".to_owned()))?;
+    let policy: import::Policy = match args.flag("--on-duplicate") {
+        true => args
+            .value("--on-duplicate")
+            .and_then(|p| p.parse().ok())
+            .ok_or(FlagInvalid("--on-duplicate".to_owned()))?,
+        false => import::Policy::Skip,
+    };
+    let map: Vec<(String, String)> = match args.value("--map") {
+        Some(map) => map
+            .split(',')
+            .map(|m| m.split_once('=').map(|(f, c)| (f.to_owned(), c.to_owned())))
+            .collect::<Option<Vec<(String, String)>>>()
+            .ok_or(FlagInvalid("--map".to_owned()))?,
+        None => Vec::new(),
+    };
+    let entries: Vec<import::Entry> = match format {
+        import::Format::Kdbx => {
+            let bytes: Vec<u8> = read(file.unwrap())?;
+            let pass: String = get_password(&format!("password for {}", file.unwrap()))?;
+            let (entries, notes) = kdbx::read(&bytes, &pass)?;
+            for note in notes {
+                println!("{}", note);
+            }
+            entries
+        }
+        import::Format::Pass => {
+            let (entries, notes) =
+                passstore::read(Path::new(file.unwrap()), passstore::gpg_decrypt)?;
+            for note in notes {
+                println!("{}", note);
+            }
+            entries
+        }
+        _ => {
+            let (entries, notes) = import::read(File::open(file.unwrap())?, format, &map)?;
+            for note in notes {
+                println!("{}", note);
+            }
+            entries
+        }
+    };
+
+    let vault: Vault = vault_open(args)?;
+    let existing: Result<Vec<String>> = vault.key_ls();
+    let Ok(existing) = existing else {
+        vault.close(false)?;
+        return existing.map(|_| ());
+    };
+    let planned: Vec<import::Planned> = import::plan(entries, &existing, &RESERVED, policy);
+    for p in &planned {
+        match p.action {
+            import::Action::Add => println!("add {}", p.key),
+            import::Action::Skip => println!("skip {}, the name is taken", p.key),
+            import::Action::Rename => println!("add {} as {}", p.entry.name, p.key),
+            import::Action::Overwrite => println!("overwrite {}", p.key),
+        }
+    }
+    let count: usize = planned
+        .iter()
+        .filter(|p| p.action != import::Action::Skip)
+        .count();
+    if args.flag("--dry-run") {
+        vault.close(false)?;
+        println!(
+            "dry run, {} of {} entries would be imported",
+            count,
+            planned.len()
+        );
+        return Ok(());
+    }
+
+    let imported: Result<()> = vault.transaction(|v| {
+        for p in planned.iter().filter(|p| p.action != import::Action::Skip) {
+            if v.key_get(&p.key)?.is_some() {
+                v.key_del(&p.key)?;
+            }
+            v.key_new(&p.key, &p.entry.password)?;
+            v.meta_set(&p.key, "username", p.entry.username.as_deref())?;
+            v.meta_set(&p.key, "url", p.entry.url.as_deref())?;
+            for (field, value) in &p.entry.meta {
+                v.meta_set(&p.key, field, Some(value))?;
+            }
+        }
+        Ok(())
+    });
+    vault.close(imported.is_ok())?;
+    imported?;
+
+    println!("imported {} of {} entries", count, planned.len());
+    Ok(())
+}
+
+/// Write every key in the vault and its' metadata to `-o` or stdout,
+/// encrypted with a new password unless `--plaintext` is given.
+///
+/// Archives are encrypted like a vault password slot, with `crypt::aes256_encrypt`.
+fn export(args: &Args) -> Result<()> {
+    let format: export::Format = match args.value("--format") {
+        Some(format) => format
+            .parse()
+            .map_err(|_| FlagInvalid("--format".to_owned()))?,
+        None => export::Format::Json,
+    };
+    let output: Option<&String> = args.value("-o");
+    if output.is_none() && stdout().is_terminal() && !args.flag("--force") {
+        return Err(StdoutTerminal);
+    }
+
+    let vault: Vault = vault_open(args)?;
+    let records: Result<Vec<export::Record>> = vault.key_ls().and_then(|keys| {
+        keys.into_iter()
+            .map(|key| {
+                Ok(export::Record {
+                    value: vault.key_get(&key)?.unwrap_or_default(),
+                    meta: vault.meta_ls(&key)?,
+                    key,
+                })
+            })
+            .collect()
+    });
+    let records: Result<Vec<export::Record>> = records.and_then(|records| {
+        vault.audit("export", None)?;
+        Ok(records)
+    });
+    let cost: Option<u8> = vault.cost();
+    vault.close(records.is_ok())?;
+    let records: Vec<export::Record> = records?;
+
+    if args.flag("--plaintext") {
+        match output {
+            Some(path) => {
+                let mut contents: Vec<u8> = Vec::new();
+                export::write(&records, format, &mut contents)?;
+                write_private(Path::new(path), &contents)?;
+            }
+            None => export::write(&records, format, stdout().lock())?,
+        }
+    } else {
+        let pass: String = get_password_confirm("password for the archive", args, &password::NEW)?;
+        let cost: u8 = match cost {
+            Some(cost) if !args.flag("--cost") && !args.flag("--unlock-ms") => cost,
+            _ => get_cost(args)?,
+        };
+        let mut contents: Vec<u8> = Vec::new();
+        export::write(&records, format, &mut contents)?;
+        let archive: Vec<u8> = crypt::aes256_encrypt(&contents, &pass, cost)?;
+        match output {
+            Some(path) => write_private(Path::new(path), &archive)?,
+            None => {
+                let mut stdout = stdout();
+                stdout.write_all(&archive)?;
+                stdout.flush()?;
+            }
+        }
+    }
+    eprintln!("exported {} keys", records.len());
+    Ok(())
+}
+
+/// Get every key in `vault` with its' metadata and when it last changed.
+fn vault_entries(vault: &Vault) -> Result<BTreeMap<String, merge::Entry>> {
+    let mut entries: BTreeMap<String, merge::Entry> = BTreeMap::new();
+    for key in vault.key_ls()? {
+        let entry: merge::Entry = merge::Entry {
+            value: vault.key_get(&key)?.unwrap_or_default(),
+            meta: vault.meta_ls(&key)?,
+            modified: vault.key_modified(&key)?.unwrap_or(0),
+        };
+        entries.insert(key, entry);
+    }
+    Ok(entries)
+}
+
+/// Ask how to resolve the conflict over `key`, unless `--on-conflict` says.
+fn merge_resolve(
+    key: &str,
+    mine: &merge::Entry,
+    theirs: &merge::Entry,
+    on_conflict: Option<merge::Resolution>,
+) -> Result<merge::Resolution> {
+    if let Some(resolution) = on_conflict {
+        return Ok(resolution);
+    }
+    let now: u64 = vault::now();
+    eprintln!(
+        "{} changed in both vaults: yours {}, theirs {}",
+        key,
+        merge::ago(mine.modified, now),
+        merge::ago(theirs.modified, now)
+    );
+    loop {
+        eprint!("keep [m]ine, [t]heirs, or [b]oth: ");
+        let mut choice: String = String::new();
+        if stdin().read_line(&mut choice)? == 0 {
+            return Err(NoParam);
+        }
+        if let Ok(resolution) = choice.trim().parse() {
+            return Ok(resolution);
+        }
+    }
+}
+
+/// Copy `entry` into `vault` as `key`, replacing any key already there.
+fn merge_copy(vault: &Vault, key: &str, entry: &merge::Entry) -> Result<()> {
+    if vault.key_get(key)?.is_some() {
+        vault.key_del(key)?;
+    }
+    vault.key_new(key, &entry.value)?;
+    for (field, value) in &entry.meta {
+        vault.meta_set(key, field, Some(value))?;
+    }
+    vault.key_touch(key, entry.modified)
+}
+
+/// Get how to resolve every merge conflict from `--on-conflict`, or `None` to ask each time.
+fn merge_on_conflict(args: &Args) -> Result<Option<merge::Resolution>> {
+    match args.flag("--on-conflict") {
+        true => Ok(Some(
+            args.value("--on-conflict")
+                .and_then(|r| r.parse().ok())
+                .ok_or(FlagInvalid("--on-conflict".to_owned()))?,
+        )),
+        false => Ok(None),
+    }
+}
+
+/// Bring the keys in `theirs`, read from the vault at `source`, into `vault` as `merge::plan` says,
+/// resolving conflicts as `on_conflict` says or by asking,
+/// and add the events of its' audit log, `their_events`, that `vault` does not have.
+fn merge_into(
+    vault: &Vault,
+    source: &str,
+    theirs: &BTreeMap<String, merge::Entry>,
+    their_events: &[audit::Event],
+    on_conflict: Option<merge::Resolution>,
+) -> Result<Vec<merge::Merged>> {
+    let mine: BTreeMap<String, merge::Entry> = vault_entries(vault)?;
+    let state: String = merge::merged_state(source);
+    let last: u64 = vault
+        .state_get(&state)?
+        .and_then(|m| m.parse().ok())
+        .unwrap_or(0);
+    let deleted: BTreeMap<String, u64> = vault.trash_ls()?.into_iter().collect();
+    let changes: Vec<(String, merge::Change)> = merge::plan(&mine, theirs, &deleted, last);
+
+    let conflicts: usize = changes
+        .iter()
+        .filter(|(_, c)| *c == merge::Change::Conflict)
+        .count();
+    if conflicts > 0 && on_conflict.is_none() && !stdin().is_terminal() {
+        return Err(MergeConflict(conflicts));
+    }
+    let mut merged: Vec<merge::Merged> = Vec::new();
+    for (key, change) in changes {
+        let resolution: Option<merge::Resolution> = match change {
+            merge::Change::Conflict => Some(merge_resolve(
+                &key,
+                &mine[&key],
+                &theirs[&key],
+                on_conflict,
+            )?),
+            _ => None,
+        };
+        merged.push((key, change, resolution));
+    }
+
+    vault.transaction(|v| {
+        v.audit_merge(their_events)?;
+        for (key, change, resolution) in &merged {
+            match (change, resolution) {
+                (merge::Change::Keep | merge::Change::Deleted, _)
+                | (_, Some(merge::Resolution::Mine)) => {}
+                (_, Some(merge::Resolution::Both)) => {
+                    merge_copy(v, &merge::rename(key, &mine, theirs), &theirs[key])?
+                }
+                _ => merge_copy(v, key, &theirs[key])?,
+            }
+        }
+        v.state_set(&state, &vault::now().to_string())
+    })?;
+    Ok(merged)
+}
+
+/// Get the audit log of `vault` to carry into another copy of it.
+///
+/// Returns `Err(AuditBroken)` if it was changed or had events removed, so they are not carried over.
+fn vault_audit(vault: &Vault) -> Result<Vec<audit::Event>> {
+    if let Some(i) = vault.audit_verify()? {
+        return Err(AuditBroken(i + 1));
+    }
+    vault.audit_ls()
+}
+
+/// Print what merging `other` did with each of its' keys, and a summary.
+fn merge_report(other: &str, merged: &[merge::Merged]) {
+    let count = |change: merge::Change| merged.iter().filter(|(_, c, _)| *c == change).count();
+    let resolved = |resolution: merge::Resolution| {
+        merged
+            .iter()
+            .filter(|(_, _, r)| *r == Some(resolution))
+            .count()
+    };
+    for (key, change, resolution) in merged {
+        match (change, resolution) {
+            (merge::Change::Add, _) => println!("add {}", key),
+            (merge::Change::Update, _) => println!("update {}", key),
+            (merge::Change::Keep, _) => println!("keep {}, yours is newer", key),
+            (merge::Change::Deleted, _) => println!("leave {} deleted, you removed it", key),
+            (_, Some(merge::Resolution::Mine)) => println!("keep {}, yours was chosen", key),
+            (_, Some(merge::Resolution::Theirs)) => println!("update {}, theirs was chosen", key),
+            (_, _) => println!("keep {} and add theirs as a copy", key),
+        }
+    }
+    println!(
+        "merged {}: {} added, {} updated, {} kept, {} left deleted, {} conflict(s) ({} mine, {} theirs, {} both)",
+        other,
+        count(merge::Change::Add),
+        count(merge::Change::Update),
+        count(merge::Change::Keep),
+        count(merge::Change::Deleted),
+        count(merge::Change::Conflict),
+        resolved(merge::Resolution::Mine),
+        resolved(merge::Resolution::Theirs),
+        resolved(merge::Resolution::Both)
+    );
+}
+
+/// Bring the changes in the vault at `other` into the active vault,
+/// asking how to resolve keys that changed in both.
+///
+/// The other vault is left as it was.
+fn vault_merge(other: &Option<&String>, args: &Args) -> Result<()> {
+    param_check(other)?;
+    let other: &str = other.unwrap();
+    let on_conflict: Option<merge::Resolution> = merge_on_conflict(args)?;
+
+    let their_vault: Vault = vault_open_path(other, &format!("password for {}", other), args)?;
+    let theirs: Result<(BTreeMap<String, merge::Entry>, Vec<audit::Event>)> =
+        vault_entries(&their_vault).and_then(|entries| Ok((entries, vault_audit(&their_vault)?)));
+    their_vault.close(false)?;
+    let (theirs, their_events) = theirs?;
+
+    let vault: Vault = vault_open(args)?;
+    let merged: Result<Vec<merge::Merged>> =
+        merge_into(&vault, other, &theirs, &their_events, on_conflict);
+    vault.close(merged.is_ok())?;
+    merge_report(other, &merged?);
+    Ok(())
+}
+
+/// Commit the active vault to its' sync repository, if it has one and `action` changed it.
+///
+/// The commit names the key or file `action` was given only if `sync_key_names` is set.
+fn sync_commit(action: &str, param: Option<&String>) {
+    let Ok(path) = vault_check() else {
+        return;
+    };
+    let repo: sync::Repo = sync::Repo::of(&path);
+    if !repo.exists() {
+        return;
+    }
+    let names: bool = cfg::get_setting_parse("sync_key_names")
+        .ok()
+        .flatten()
+        .unwrap_or(false);
+    let message: String = match (names, param) {
+        (true, Some(param)) => format!("srpk {} {}", action, param),
+        _ => format!("srpk {}", action),
+    };
+    if let Err(e) = repo.commit(&message) {
+        eprintln!("warning: could not commit the vault for sync: {}", e);
+    }
+}
+
+/// Keep the history of the active vault in a git repository, synced with `remote`.
+fn sync_init(remote: &Option<&String>, args: &Args) -> Result<()> {
+    param_check(remote)?;
+    let path: String = vault_check()?;
+    let repo: sync::Repo = sync::Repo::of(&path);
+    if repo.exists() {
+        return Err(PathTaken(PathBuf::from(format!("{}.git", path))));
+    }
+    repo.init(remote.unwrap())?;
+    println!("vault history is now kept in {}.git", path);
+    sync(args)
+}
+
+/// Exchange changes to the active vault with its' sync remote,
+/// merging them as `srpk merge` does if both sides changed it.
+fn sync(args: &Args) -> Result<()> {
+    let path: String = vault_check()?;
+    let repo: sync::Repo = sync::repo(&path)?;
+    repo.commit("srpk sync")?;
+    match repo.fetch()? {
+        sync::Status::UpToDate => println!("vault is up to date with the remote"),
+        sync::Status::Ahead => {
+            repo.push()?;
+            println!("sent changes to the remote");
+        }
+        sync::Status::Behind => {
+            repo.fast_forward()?;
+            println!("got changes from the remote");
+        }
+        sync::Status::Diverged => {
+            let on_conflict: Option<merge::Resolution> = merge_on_conflict(args)?;
+            let remote_path: String = format!("{}.remote", path);
+            std::fs::write(&remote_path, repo.remote_vault()?)?;
+            let theirs: Result<(BTreeMap<String, merge::Entry>, Vec<audit::Event>)> = (|| {
+                let their_vault: Vault =
+                    vault_open_path(&remote_path, "password for the remote vault", args)?;
+                let theirs = vault_entries(&their_vault)
+                    .and_then(|entries| Ok((entries, vault_audit(&their_vault)?)));
+                their_vault.close(false)?;
+                theirs
+            })(
+            );
+            std::fs::remove_file(&remote_path)?;
+            let (theirs, their_events) = theirs?;
+
+            let vault: Vault = vault_open(args)?;
+            let merged: Result<Vec<merge::Merged>> =
+                merge_into(&vault, &remote_path, &theirs, &their_events, on_conflict);
+            vault.close(merged.is_ok())?;
+            merge_report("the remote", &merged?);
+            repo.commit_merge("srpk sync merge")?;
+            repo.push()?;
+            println!("sent merged vault to the remote");
+        }
+    }
+    Ok(())
+}
+
+/// Exchange operation logs with other copies of the active vault through the folder `--dir`,
+/// applying their changes without asking: the latest change to each key wins.
+///
+/// Each device writes only its' own log, `<device>.srpklog`, encrypted with the vault's data key,
+/// so any file syncing service can share the folder.
+fn sync_dir(args: &Args) -> Result<()> {
+    let dir: PathBuf = PathBuf::from(args.value("--dir").ok_or(FlagInvalid("--dir".to_owned()))?);
+    if !dir.is_dir() {
+        return Err(PathEmpty(dir));
+    }
+    let device: String = cfg::get_device_id()?;
+    let own: PathBuf = dir.join(format!("{}.{}", device, oplog::LOG_EXTENSION));
+    let mut logs: Vec<PathBuf> = std::fs::read_dir(&dir)?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|e| e == oplog::LOG_EXTENSION) && *p != own)
+        .collect();
+    logs.sort();
+
+    let vault: Vault = vault_open(args)?;
+    let key: [u8; 32] = vault.data_key();
+    let synced: Result<(usize, Vec<u8>)> = vault.transaction(|v| {
+        v.oplog_enable()?;
+        v.oplog_seed()?;
+        let mut applied: usize = 0;
+        for log in &logs {
+            let (ops, events) = crypt::aes256_open(&read(log)?, &key)
+                .ok()
+                .and_then(|bytes| oplog::decode(&bytes).ok())
+                .ok_or_else(|| {
+                    SyncLogInvalid(format!(
+                        "{} is not from a copy of this vault, or the vault was rekeyed since",
+                        log.display()
+                    ))
+                })?;
+            if let Some(i) = v.audit_check(&events) {
+                return Err(SyncLogInvalid(format!(
+                    "{} has an audit log that was changed or had events removed at event {}",
+                    log.display(),
+                    i + 1
+                )));
+            }
+            v.audit_merge(&events)?;
+            applied += v.oplog_apply(&ops)?;
+        }
+        Ok((
+            applied,
+            crypt::aes256_seal(&oplog::encode(&v.oplog()?, &v.audit_ls()?), &key)?,
+        ))
+    });
+    vault.close(synced.is_ok())?;
+    let (applied, log) = synced?;
+
+    // write it whole before replacing the old log, so others never read half of it
+    let temp: PathBuf = own.with_extension("tmp");
+    std::fs::write(&temp, log)?;
+    std::fs::rename(&temp, &own)?;
+    println!(
+        "applied {} change(s) from {} other device(s), and wrote {}",
+        applied,
+        logs.len(),
+        own.display()
+    );
+    Ok(())
+}
+
+/// Show which keys and metadata fields differ between the vaults at `a` and `b`,
+/// without their values unless `--show-secrets` is given.
+fn vault_diff(a: &Option<&String>, b: &Option<&String>, args: &Args) -> Result<()> {
+    param_check(a)?;
+    param_check(b)?;
+    let mut vaults: Vec<BTreeMap<String, merge::Entry>> = Vec::new();
+    for path in [a.unwrap(), b.unwrap()] {
+        let vault: Vault = vault_open_path(path, &format!("password for {}", path), args)?;
+        let entries: Result<BTreeMap<String, merge::Entry>> = vault_entries(&vault);
+        vault.close(false)?;
+        vaults.push(entries?);
+    }
+
+    let show: bool = args.flag("--show-secrets");
+    let value = |v: &Option<String>| v.clone().unwrap_or_else(|| "(unset)".to_owned());
+    let diffs: Vec<(String, merge::Diff)> = merge::diff(&vaults[0], &vaults[1]);
+    for (key, diff) in &diffs {
+        match diff {
+            merge::Diff::Added => println!("+ {}", key),
+            merge::Diff::Removed => println!("- {}", key),
+            merge::Diff::Changed(fields) => {
+                println!("~ {}", key);
+                for (field, old, new) in fields {
+                    let sign: char = match (old, new) {
+                        (None, _) => '+',
+                        (_, None) => '-',
+                        _ => '~',
+                    };
+                    match show {
+                        true => {
+                            println!("    {} {}: {} -> {}", sign, field, value(old), value(new))
+                        }
+                        false => println!("    {} {}", sign, field),
+                    }
+                }
+            }
+        }
+    }
+    match diffs.is_empty() {
+        true => println!("vaults have the same keys"),
+        false => println!(
+            "{} added, {} removed, {} changed",
+            diffs
+                .iter()
+                .filter(|(_, d)| *d == merge::Diff::Added)
+                .count(),
+            diffs
+                .iter()
+                .filter(|(_, d)| *d == merge::Diff::Removed)
+                .count(),
+            diffs
+                .iter()
+                .filter(|(_, d)| matches!(d, merge::Diff::Changed(_)))
+                .count()
+        ),
+    }
+    Ok(())
+}
+
 fn help() {
     println!(
         "srpk v{} 
 
 create or target srpk vault:
-    init <vault>    create a new vault at directory <vault>
-    use <vault>     set <vault> as active vault
-    which           see which vault is currently active
+    init <vault>      create a new vault at directory <vault>
+    use <vault>       set <vault> as active vault
+    which             see which vault is currently active
+    bench             see how long unlocking takes at each crypt cost
+    verify [<vault>]  check that <vault> (or the active vault) is intact
 
 work with the active vault:
     ls              see keys in vault
     mk <key>        create new password with name <key>
-    rm <key>        remove existing password with name <key>
+                    (--username <name> to store a username with it)
+    rm <key>        move password <key> to the trash
+    trash ls        see keys in the trash and when they were removed
+    trash restore <key>
+                    bring key <key> back from the trash
+    trash empty     permanently delete every key in the trash; keys are also deleted
+                    once they have been in it for trash_retention_days
+    audit           see when keys were got, made, changed, removed, restored, purged, synced
+                    or exported, ssh keys loaded into the agent, and the vault rekeyed
+                    or split for recovery, by which user on which host, checking that
+                    none of the log was changed or removed; merges and syncs carry
+                    it between copies of the vault
     <key>           get existing password with name <key>
+                    (--timeout <secs> to change how long it stays in the clipboard,
+                     --wait to wait and count down until it is cleared)
+    get <key>       same as <key>, or for scripts:
+                    --stdout to print it instead (--force if stdout is a terminal),
+                    --json to print the key, username and password as JSON,
+                    --no-newline to leave off the trailing newline
+    run --env <NAME>=<key> ... -- <command>
+                    run <command> with password <key> in environment variable <NAME>
+    inject -i <template> [-o <file>]
+                    fill in each {{{{ srpk \"<key>\" }}}} (or {{{{ srpk \"<key>\" \"username\" }}}})
+                    in <template> and write it to <file>, readable only by you
+    git-credential get|store|erase
+                    act as a git credential helper, keeping credentials as git/<host>;
+                    link srpk as git-credential-srpk and `git config credential.helper srpk`
+    docker-credential get|store|erase|list
+                    act as a docker credential helper, keeping logins as docker/<server>;
+                    link srpk as docker-credential-srpk and set \"credsStore\": \"srpk\"
+                    in ~/.docker/config.json
+
+move to srpk from another password manager:
+    import <file> --format <format>
+                    add the logins in the CSV export <file>, where <format> is one of
+                    bitwarden, lastpass, 1password, chrome, firefox or generic
+                     (other columns, such as notes, become metadata),
+                    or in the KeePass database <file> with kdbx (KDBX 4, password only;
+                     groups become key paths, notes, fields and attachments metadata)
+                    or in the pass password store directory <file> with pass (decrypted
+                     with gpg; the first line is the password, key: value lines metadata)
+                    (--map <field>=<column>,... to read name, username, password or url
+                     from another column, --on-duplicate skip|rename|overwrite for names
+                     already in the vault (default skip), --dry-run to only show what
+                     would be imported)
+
+move out of the active vault:
+    export [-o <file>]
+                    write every key and its' metadata to <file> (or stdout), as
+                    --format json (default) or csv, in an archive encrypted with a new
+                    password; --plaintext to write it unencrypted instead
+
+compare and combine copies of a vault:
+    diff <a> <b>    see which keys and metadata fields were added (+), removed (-)
+                    or changed (~) from vault <a> to vault <b>
+                    (--show-secrets to also print their values)
+    merge <vault>   bring the changes in <vault> into the active vault, asking which
+                    to keep of keys changed in both since the last merge with <vault>
+                    (--on-conflict mine|theirs|both to not ask)
+    sync init <remote>
+                    keep the history of the active vault in git, committing every change,
+                    and sync it with <remote> (a new bare repository if it is a path that
+                    does not exist yet); every copy needs the same vault password
+    sync            send and get changes to and from the remote, merging if both changed
+                    (--on-conflict as for merge)
+    sync --dir <folder>
+                    exchange changes with other copies of the active vault through
+                    <folder>, such as a shared or synced folder; the latest change to
+                    each key wins, so nothing needs resolving
+
+use ssh keys from the active vault:
+    ssh import <file> [<name>]  store the ssh private key at <file> as ssh/<name>
+                                (--confirm to ask before each use,
+                                 --lifetime <secs> to forget it after the agent has run that long)
+    ssh-agent                   serve ssh/ keys to ssh over a unix socket
+                                (--socket <path> to choose where, and --confirm and
+                                 --lifetime <secs> for every key); it stays in the
+                                foreground, so run it in another terminal and export
+                                the SSH_AUTH_SOCK it prints where you use ssh
+
+share the active vault with public keys:
+    keygen <file>           create a new identity file at <file>
+    recipient add <pubkey>  allow the owner of <pubkey> to open the vault
+    recipient rm <pubkey>   remove a recipient from the vault
+    recipient ls            see recipients of the vault
+
+recover the active vault:
+    rekey                                       set a new password and data key
+    recovery split --threshold <n> --shares <m> print <m> shares, any <n> of which recover the vault
+    recovery combine                            enter shares to recover and rekey the vault
+
+set SRPK_IDENTITY=<file> to open vaults with an identity instead of a password
+
+to run without a terminal, e.g. from cron or CI, give the vault password with
+--password-fd <fd> or --password-file <file>, or set SRPK_PASSWORD_COMMAND=<command>
+to use the first line printed by <command>; mk and rekey take --new-password-fd <fd>
+and --new-password-file <file> for the new password
+
+init and rekey choose a crypt cost that unlocks in about 1 second;
+change this with --unlock-ms <ms>, or pick a cost directly with --cost <5-31>
+
+change settings:
+    config                  see changed settings
+    config <name> [<value>] see or change a setting (--unset to reset it)
+
+settings:
+    audit_reads             false to stop recording each password read in the audit log,
+                            which rewrites the vault every time (default true)
+    clipboard_timeout       seconds before the clipboard is cleared (default 10)
+    clipboard_backend       where secrets are put, tried in order (default auto):
+                            any of system, primary (X11/Wayland selection),
+                            osc52 (terminal escape codes, works over ssh),
+                            separated by commas; auto is system,osc52
+    sync_key_names          true to name the key changed in each sync commit (default false)
+    trash_retention_days    days removed keys stay in the trash (default 30)
 
-srpk will clear your clipboard 10 seconds after use",
+srpk will restore your clipboard in the background 10 seconds after use",
         env!("CARGO_PKG_VERSION")
     )
 }
@@ -0,0 +1,369 @@
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{absolute, PathBuf},
+    str::FromStr,
+};
+
+/// Prefix of the vault states holding when the vault was last merged with each other vault.
+const MERGED_STATE: &str = "merged";
+
+/// Get the name of the vault state holding when the vault was last merged with the vault at `source`.
+///
+/// Each source has its' own, so merging with one vault says nothing about changes to another.
+pub fn merged_state(source: &str) -> String {
+    let path: PathBuf = absolute(source).unwrap_or_else(|_| PathBuf::from(source));
+    let hash: String = Sha256::digest(path.to_string_lossy().as_bytes())[..8]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("{}:{}", MERGED_STATE, hash)
+}
+
+/// A key as it is in one vault.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub value: String,
+    pub meta: Vec<(String, String)>,
+    /// When the key last changed, in seconds since the unix epoch.
+    pub modified: u64,
+}
+
+impl Entry {
+    fn same(&self, other: &Entry) -> bool {
+        self.value == other.value && self.meta == other.meta
+    }
+}
+
+/// What merging will do with a key of the other vault.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Change {
+    /// Only the other vault has it, so it is copied over.
+    Add,
+    /// This vault deleted it after the other last changed it, so it stays deleted.
+    Deleted,
+    /// Only the other vault changed it since the last merge, so it replaces ours.
+    Update,
+    /// Only this vault changed it since the last merge, so ours is kept.
+    Keep,
+    /// Both vaults changed it since the last merge.
+    Conflict,
+}
+
+/// A key of the other vault, what merging did with it, and how it was resolved if it conflicted.
+pub type Merged = (String, Change, Option<Resolution>);
+
+/// How to resolve a conflict.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Resolution {
+    Mine,
+    Theirs,
+    /// Keep ours, and copy theirs over under a new name.
+    Both,
+}
+
+impl FromStr for Resolution {
+    type Err = ();
+
+    fn from_str(s: &str) -> core::result::Result<Self, ()> {
+        match s {
+            "mine" | "m" => Ok(Resolution::Mine),
+            "theirs" | "t" => Ok(Resolution::Theirs),
+            "both" | "b" => Ok(Resolution::Both),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Decide what to do with each key of `theirs` that differs from `mine`, given when
+/// this vault was last `merged` and when each key in its' trash was `deleted`;
+/// keys that are the same in both are left out.
+///
+/// A key changed in only one vault since the last merge is taken from that vault.
+/// Keys changed in both are conflicts, as are all differences before the first merge.
+/// A key this vault deleted is not brought back unless the other changed it since.
+/// Keys only in this vault are kept, since nothing says whether the other deleted them.
+pub fn plan(
+    mine: &BTreeMap<String, Entry>,
+    theirs: &BTreeMap<String, Entry>,
+    deleted: &BTreeMap<String, u64>,
+    merged: u64,
+) -> Vec<(String, Change)> {
+    let mut changes: Vec<(String, Change)> = Vec::new();
+    for (key, their) in theirs {
+        let change: Change = match mine.get(key) {
+            None if deleted.get(key).is_some_and(|d| *d >= their.modified) => Change::Deleted,
+            None => Change::Add,
+            Some(my) if my.same(their) => continue,
+            Some(my) if my.modified > merged && their.modified > merged => Change::Conflict,
+            Some(my) if their.modified > my.modified => Change::Update,
+            Some(_) => Change::Keep,
+        };
+        changes.push((key.clone(), change));
+    }
+    changes
+}
+
+/// How a key differs between two vaults.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Diff {
+    /// Only in the second vault.
+    Added,
+    /// Only in the first vault.
+    Removed,
+    /// In both, with these fields differing; the password is the field `password`.
+    Changed(Vec<(String, Option<String>, Option<String>)>),
+}
+
+/// Compare every key of vault `a` against vault `b`, leaving out keys that are the same.
+///
+/// Each changed field is given with its' value in `a` and in `b`, or `None` if it is unset.
+pub fn diff(a: &BTreeMap<String, Entry>, b: &BTreeMap<String, Entry>) -> Vec<(String, Diff)> {
+    let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+    let mut diffs: Vec<(String, Diff)> = Vec::new();
+    for key in keys {
+        let (before, after) = match (a.get(key), b.get(key)) {
+            (Some(before), Some(after)) => (before, after),
+            (None, _) => {
+                diffs.push((key.clone(), Diff::Added));
+                continue;
+            }
+            (_, None) => {
+                diffs.push((key.clone(), Diff::Removed));
+                continue;
+            }
+        };
+        let mut fields: Vec<(String, Option<String>, Option<String>)> = Vec::new();
+        if before.value != after.value {
+            fields.push((
+                "password".to_owned(),
+                Some(before.value.clone()),
+                Some(after.value.clone()),
+            ));
+        }
+        let get = |entry: &Entry, field: &str| -> Option<String> {
+            entry
+                .meta
+                .iter()
+                .find(|(f, _)| f == field)
+                .map(|(_, v)| v.clone())
+        };
+        let names: BTreeSet<&String> = before
+            .meta
+            .iter()
+            .chain(&after.meta)
+            .map(|(f, _)| f)
+            .collect();
+        for name in names {
+            let (old, new) = (get(before, name), get(after, name));
+            if old != new {
+                fields.push((name.clone(), old, new));
+            }
+        }
+        if !fields.is_empty() {
+            diffs.push((key.clone(), Diff::Changed(fields)));
+        }
+    }
+    diffs
+}
+
+/// Get a name for a copy of `key` that is in neither `mine` nor `theirs`.
+pub fn rename(
+    key: &str,
+    mine: &BTreeMap<String, Entry>,
+    theirs: &BTreeMap<String, Entry>,
+) -> String {
+    (2..)
+        .map(|n| format!("{}-{}", key, n))
+        .find(|k| !mine.contains_key(k) && !theirs.contains_key(k))
+        .unwrap()
+}
+
+/// Describe how long before `now` the time `then` was, such as `3 hours ago`.
+pub fn ago(then: u64, now: u64) -> String {
+    if then == 0 {
+        return "at an unknown time".to_owned();
+    }
+    let secs: u64 = now.saturating_sub(then);
+    let (n, unit): (u64, &str) = match secs {
+        0..=59 => return "just now".to_owned(),
+        60..=3599 => (secs / 60, "minute"),
+        3600..=86399 => (secs / 3600, "hour"),
+        _ => (secs / 86400, "day"),
+    };
+    format!("{} {}{} ago", n, unit, if n == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vault::Vault;
+
+    fn entry(value: &str, modified: u64) -> Entry {
+        Entry {
+            value: value.to_owned(),
+            meta: Vec::new(),
+            modified,
+        }
+    }
+
+    fn vault(entries: &[(&str, Entry)]) -> BTreeMap<String, Entry> {
+        entries
+            .iter()
+            .map(|(k, e)| (k.to_string(), e.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_plan() {
+        let mine = vault(&[
+            ("same", entry("a", 10)),
+            ("ours", entry("new", 30)),
+            ("theirs", entry("old", 10)),
+            ("both", entry("mine", 30)),
+            ("only-mine", entry("a", 30)),
+        ]);
+        let theirs = vault(&[
+            ("same", entry("a", 40)),
+            ("ours", entry("old", 10)),
+            ("theirs", entry("new", 30)),
+            ("both", entry("theirs", 40)),
+            ("added", entry("a", 5)),
+        ]);
+        let none: BTreeMap<String, u64> = BTreeMap::new();
+        assert_eq!(
+            plan(&mine, &theirs, &none, 20),
+            vec![
+                ("added".to_owned(), Change::Add),
+                ("both".to_owned(), Change::Conflict),
+                ("ours".to_owned(), Change::Keep),
+                ("theirs".to_owned(), Change::Update),
+            ]
+        );
+        // before the first merge, nothing says which differences are changes
+        assert_eq!(plan(&mine, &theirs, &none, 0)[2].1, Change::Conflict);
+
+        let mut meta: Entry = entry("a", 40);
+        meta.meta.push(("username".to_owned(), "me".to_owned()));
+        let theirs = vault(&[("same", meta)]);
+        assert_eq!(
+            plan(&mine, &theirs, &none, 20),
+            vec![("same".to_owned(), Change::Update)]
+        );
+    }
+
+    #[test]
+    fn test_plan_deleted() {
+        let mine = vault(&[]);
+        let theirs = vault(&[("deleted", entry("a", 10)), ("changed", entry("b", 40))]);
+        let deleted: BTreeMap<String, u64> =
+            BTreeMap::from([("deleted".to_owned(), 30), ("changed".to_owned(), 30)]);
+        // deleted here after the last merge: it stays deleted, unless they changed it since
+        assert_eq!(
+            plan(&mine, &theirs, &deleted, 20),
+            vec![
+                ("changed".to_owned(), Change::Add),
+                ("deleted".to_owned(), Change::Deleted),
+            ]
+        );
+        // keys never seen here are still added
+        assert_eq!(
+            plan(&mine, &theirs, &BTreeMap::new(), 20),
+            vec![
+                ("changed".to_owned(), Change::Add),
+                ("deleted".to_owned(), Change::Add),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merged_state() {
+        assert_eq!(merged_state("b.db"), merged_state("./b.db"));
+        assert_ne!(merged_state("b.db"), merged_state("c.db"));
+        assert!(merged_state("b.db").starts_with("merged:"));
+
+        // after merging with b, an unrelated vault c still conflicts on every difference
+        let dir: &str = "merge_test_merged_state";
+        std::fs::create_dir(dir).unwrap();
+        let path: String = format!("{}/a.db", dir);
+        Vault::create(&path, "password", 5).unwrap();
+        let a: Vault = Vault::open(&path, "password").unwrap();
+        a.state_set(&merged_state("b.db"), "20").unwrap();
+        let last = |source: &str| -> u64 {
+            a.state_get(&merged_state(source))
+                .unwrap()
+                .map_or(0, |m| m.parse().unwrap())
+        };
+        let (from_b, from_c) = (last("b.db"), last("c.db"));
+        a.close(false).unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+
+        let mine = vault(&[("key", entry("old", 10))]);
+        let theirs = vault(&[("key", entry("new", 15))]);
+        assert_eq!(
+            plan(&mine, &theirs, &BTreeMap::new(), from_b),
+            vec![("key".to_owned(), Change::Update)]
+        );
+        assert_eq!(
+            plan(&mine, &theirs, &BTreeMap::new(), from_c),
+            vec![("key".to_owned(), Change::Conflict)]
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut changed: Entry = entry("old", 1);
+        changed.meta = vec![
+            ("notes".to_owned(), "hi".to_owned()),
+            ("username".to_owned(), "me".to_owned()),
+        ];
+        let a = vault(&[
+            ("same", entry("a", 1)),
+            ("changed", changed.clone()),
+            ("removed", entry("a", 1)),
+        ]);
+        changed.value = "new".to_owned();
+        changed.meta = vec![
+            ("url".to_owned(), "https://example.com".to_owned()),
+            ("username".to_owned(), "me".to_owned()),
+        ];
+        let b = vault(&[
+            ("same", entry("a", 2)),
+            ("changed", changed),
+            ("added", entry("a", 1)),
+        ]);
+
+        let some = |v: &str| Some(v.to_owned());
+        assert_eq!(
+            diff(&a, &b),
+            vec![
+                ("added".to_owned(), Diff::Added),
+                (
+                    "changed".to_owned(),
+                    Diff::Changed(vec![
+                        ("password".to_owned(), some("old"), some("new")),
+                        ("notes".to_owned(), some("hi"), None),
+                        ("url".to_owned(), None, some("https://example.com")),
+                    ])
+                ),
+                ("removed".to_owned(), Diff::Removed),
+            ]
+        );
+        assert!(diff(&a, &a).is_empty());
+    }
+
+    #[test]
+    fn test_rename() {
+        let mine = vault(&[("mail", entry("a", 1)), ("mail-2", entry("a", 1))]);
+        let theirs = vault(&[("mail", entry("b", 1)), ("mail-3", entry("b", 1))]);
+        assert_eq!(rename("mail", &mine, &theirs), "mail-4");
+    }
+
+    #[test]
+    fn test_ago() {
+        assert_eq!(ago(0, 100), "at an unknown time");
+        assert_eq!(ago(100, 130), "just now");
+        assert_eq!(ago(100, 100 + 3600), "1 hour ago");
+        assert_eq!(ago(100, 100 + 3 * 86400), "3 days ago");
+    }
+}
@@ -0,0 +1,181 @@
+use serde_json::{json, Value};
+
+use crate::audit::Event;
+use crate::errors::{Result, SrpkError::SyncLogInvalid};
+
+/// Extension of the log file each device writes to a `srpk sync --dir` folder.
+pub const LOG_EXTENSION: &str = "srpklog";
+
+/// The last change to a key, as recorded in the vault's operation log.
+///
+/// Each key keeps only its' latest operation, which holds the whole entry,
+/// so copies of the vault agree once they have seen the same operations, in any order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Op {
+    pub key: String,
+    /// Lamport clock of the change: higher than that of any change the device had seen.
+    pub clock: u64,
+    /// The device that made the change.
+    pub device: String,
+    /// The password, or `None` if the key was deleted.
+    pub value: Option<String>,
+    pub meta: Vec<(String, String)>,
+}
+
+impl Op {
+    /// Whether this operation replaces `other`: the higher clock wins,
+    /// and the higher device ID breaks ties, so every copy picks the same one.
+    pub fn wins(&self, other: &Op) -> bool {
+        (self.clock, &self.device) > (other.clock, &other.device)
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "key": self.key,
+            "clock": self.clock,
+            "device": self.device,
+            "value": self.value,
+            "meta": self.meta,
+        })
+    }
+
+    fn from_json(value: &Value) -> Option<Self> {
+        let meta: Vec<(String, String)> = value["meta"]
+            .as_array()?
+            .iter()
+            .map(|pair| Some((pair[0].as_str()?.to_owned(), pair[1].as_str()?.to_owned())))
+            .collect::<Option<Vec<(String, String)>>>()?;
+        Some(Self {
+            key: value["key"].as_str()?.to_owned(),
+            clock: value["clock"].as_u64()?,
+            device: value["device"].as_str()?.to_owned(),
+            value: match &value["value"] {
+                Value::Null => None,
+                value => Some(value.as_str()?.to_owned()),
+            },
+            meta,
+        })
+    }
+}
+
+/// Render `ops`, and the `events` of the vault's audit log, to be encrypted into a log file.
+pub fn encode(ops: &[Op], events: &[Event]) -> Vec<u8> {
+    json!({
+        "ops": ops.iter().map(Op::to_json).collect::<Vec<Value>>(),
+        "audit": events.iter().map(Event::to_json).collect::<Vec<Value>>(),
+    })
+    .to_string()
+    .into_bytes()
+}
+
+/// Read the operations and audit events of a decrypted log file.
+///
+/// Logs written before audit events were shared hold only operations.
+/// Returns `Err(SyncLogInvalid)` if it is malformed.
+pub fn decode(bytes: &[u8]) -> Result<(Vec<Op>, Vec<Event>)> {
+    let invalid = || SyncLogInvalid("log is malformed".to_owned());
+    let value: Value = serde_json::from_slice(bytes).map_err(|_| invalid())?;
+    let (ops, events): (&Vec<Value>, &[Value]) = match &value {
+        Value::Array(ops) => (ops, &[]),
+        value => (
+            value["ops"].as_array().ok_or_else(invalid)?,
+            value["audit"].as_array().ok_or_else(invalid)?,
+        ),
+    };
+    Ok((
+        ops.iter()
+            .map(|op| Op::from_json(op).ok_or_else(invalid))
+            .collect::<Result<Vec<Op>>>()?,
+        events
+            .iter()
+            .map(|event| Event::from_json(event).ok_or_else(invalid))
+            .collect::<Result<Vec<Event>>>()?,
+    ))
+}
+
+/// Pick the operations of `theirs` that replace those in `ours` for the same key,
+/// or that are for keys `ours` has no operation for.
+pub fn newer<'a>(ours: &[Op], theirs: &'a [Op]) -> Vec<&'a Op> {
+    theirs
+        .iter()
+        .filter(|their| {
+            ours.iter()
+                .find(|our| our.key == their.key)
+                .is_none_or(|our| their.wins(our))
+        })
+        .collect()
+}
+
+/// Make up a new device ID.
+pub fn device_id() -> String {
+    crate::crypt::generate_key()[..8]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn op(key: &str, clock: u64, device: &str, value: Option<&str>) -> Op {
+        Op {
+            key: key.to_owned(),
+            clock,
+            device: device.to_owned(),
+            value: value.map(str::to_owned),
+            meta: vec![("username".to_owned(), "me".to_owned())],
+        }
+    }
+
+    #[test]
+    fn test_encode() {
+        let ops: Vec<Op> = vec![op("a", 1, "d1", Some("pass")), op("b", 2, "d2", None)];
+        let chain: [u8; 32] = crate::audit::chain_key(&[1u8; 32]);
+        let events: Vec<Event> = vec![Event::new(
+            "rm",
+            Some("b"),
+            5,
+            crate::audit::GENESIS,
+            &chain,
+        )];
+        assert_eq!(
+            decode(&encode(&ops, &events)).unwrap(),
+            (ops.clone(), events)
+        );
+        // logs from before audit events were shared
+        let old: String =
+            serde_json::to_string(&ops.iter().map(Op::to_json).collect::<Vec<_>>()).unwrap();
+        assert_eq!(decode(old.as_bytes()).unwrap(), (ops, Vec::new()));
+        assert!(decode(b"[{\"key\":\"a\"}]").is_err());
+        assert!(decode(b"not json").is_err());
+    }
+
+    #[test]
+    fn test_newer() {
+        let ours: Vec<Op> = vec![
+            op("same", 3, "a", Some("x")),
+            op("older", 5, "a", Some("x")),
+            op("tie", 4, "a", Some("x")),
+        ];
+        let theirs: Vec<Op> = vec![
+            op("same", 3, "a", Some("x")),
+            op("older", 2, "b", Some("y")),
+            op("tie", 4, "b", None),
+            op("new", 1, "b", Some("y")),
+        ];
+        let keys: Vec<&str> = newer(&ours, &theirs)
+            .iter()
+            .map(|o| o.key.as_str())
+            .collect();
+        assert_eq!(keys, vec!["tie", "new"]);
+
+        // both copies pick the same winner, whichever merges first
+        let mine: Vec<&str> = newer(&theirs, &ours)
+            .iter()
+            .map(|o| o.key.as_str())
+            .collect();
+        assert_eq!(mine, vec!["older"]);
+        assert_ne!(device_id(), device_id());
+    }
+}
@@ -0,0 +1,190 @@
+use std::{
+    fs::read_dir,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use crate::errors::{
+    Result,
+    SrpkError::{ImportInvalid, RunFailed},
+};
+use crate::import::Entry;
+
+/// Fields of a `key: value` line read as the username rather than as metadata.
+const USERNAME_FIELDS: [&str; 3] = ["username", "user", "login"];
+
+/// Fields of a `key: value` line read as the url rather than as metadata.
+const URL_FIELDS: [&str; 2] = ["url", "website"];
+
+/// Decrypt the password file at `path` with the local `gpg`, which asks for the
+/// passphrase itself if gpg-agent does not have it.
+pub fn gpg_decrypt(path: &Path) -> Result<String> {
+    let output = Command::new("gpg")
+        .args(["--quiet", "--decrypt"])
+        .arg(path)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| RunFailed("gpg".to_owned(), e.to_string()))?;
+    if !output.status.success() {
+        return Err(ImportInvalid(format!(
+            "gpg could not decrypt {}",
+            path.display()
+        )));
+    }
+    String::from_utf8(output.stdout)
+        .map_err(|_| ImportInvalid(format!("{} is not text", path.display())))
+}
+
+/// Read the decrypted contents of a password file as the entry `name`.
+///
+/// The first line is the password. Following `key: value` lines are metadata,
+/// and any other lines are kept as `notes`.
+/// Returns `None` if the password is empty.
+fn parse(name: String, text: &str) -> Option<Entry> {
+    let mut lines = text.lines();
+    let password: String = lines.next().filter(|p| !p.is_empty())?.to_owned();
+    let mut entry: Entry = Entry {
+        name,
+        username: None,
+        password,
+        url: None,
+        meta: Vec::new(),
+    };
+    let mut notes: Vec<&str> = Vec::new();
+    for line in lines {
+        let Some((field, value)) = line.split_once(':').filter(|(f, _)| {
+            !f.is_empty() && !f.contains(char::is_whitespace) && f != &"http" && f != &"https"
+        }) else {
+            notes.push(line);
+            continue;
+        };
+        let value: String = value.trim().to_owned();
+        let lower: String = field.to_lowercase();
+        if USERNAME_FIELDS.contains(&lower.as_str()) && entry.username.is_none() {
+            entry.username = Some(value);
+        } else if URL_FIELDS.contains(&lower.as_str()) && entry.url.is_none() {
+            entry.url = Some(value);
+        } else {
+            entry.meta.push((field.to_owned(), value));
+        }
+    }
+    let notes: String = notes.join("\n").trim().to_owned();
+    if !notes.is_empty() {
+        entry.meta.insert(0, ("notes".to_owned(), notes));
+    }
+    Some(entry)
+}
+
+/// Collect the `.gpg` files below `dir`, in order, skipping hidden files such as `.git`.
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let mut paths: Vec<PathBuf> = read_dir(dir)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<PathBuf>>>()?;
+    paths.sort();
+    for path in paths {
+        if path
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with('.'))
+        {
+            continue;
+        }
+        if path.is_dir() {
+            walk(&path, files)?;
+        } else if path.extension().is_some_and(|e| e == "gpg") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Read the entries of the password store at `dir`, decrypting each file with `decrypt`.
+///
+/// Each key is named after its' file, below `dir` and without `.gpg`, so
+/// `email/work.gpg` becomes `email/work`.
+/// Also returns a note for each file that is not imported.
+pub fn read(
+    dir: &Path,
+    mut decrypt: impl FnMut(&Path) -> Result<String>,
+) -> Result<(Vec<Entry>, Vec<String>)> {
+    if !dir.is_dir() {
+        return Err(ImportInvalid(format!(
+            "{} is not a password store directory",
+            dir.display()
+        )));
+    }
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk(dir, &mut files)?;
+
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut notes: Vec<String> = Vec::new();
+    for file in files {
+        let name: Vec<String> = file
+            .strip_prefix(dir)
+            .unwrap()
+            .with_extension("")
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        let name: String = name.join("/");
+        match parse(name.clone(), &decrypt(&file)?) {
+            Some(entry) => entries.push(entry),
+            None => notes.push(format!("{} has no password and was not imported", name)),
+        }
+    }
+    Ok((entries, notes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir, create_dir_all, read_to_string, remove_dir_all, write};
+
+    #[test]
+    fn test_parse() {
+        let entry: Entry = parse(
+            "mail".to_owned(),
+            "hunter2\nlogin: me\nURL: https://mail.example.com\npin: 1234\n\nsee https://example.com\n",
+        )
+        .unwrap();
+        assert_eq!(entry.password, "hunter2");
+        assert_eq!(entry.username.as_deref(), Some("me"));
+        assert_eq!(entry.url.as_deref(), Some("https://mail.example.com"));
+        assert_eq!(
+            entry.meta,
+            vec![
+                ("notes".to_owned(), "see https://example.com".to_owned()),
+                ("pin".to_owned(), "1234".to_owned()),
+            ]
+        );
+        assert!(parse("empty".to_owned(), "\nlogin: me\n").is_none());
+    }
+
+    #[test]
+    fn test_read() {
+        let dir: &str = "test_passstore_read";
+        create_dir(dir).unwrap();
+        create_dir_all(format!("{}/email/work", dir)).unwrap();
+        create_dir(format!("{}/.git", dir)).unwrap();
+        write(format!("{}/.gpg-id", dir), "me@example.com\n").unwrap();
+        write(format!("{}/.git/config.gpg", dir), "ignored\n").unwrap();
+        write(format!("{}/github.gpg", dir), "hunter2\n").unwrap();
+        write(format!("{}/email/work/me.gpg", dir), "s3cret\nuser: me\n").unwrap();
+        write(format!("{}/email/empty.gpg", dir), "\n").unwrap();
+        write(format!("{}/email/readme.txt", dir), "not a password\n").unwrap();
+
+        let result = read(Path::new(dir), |p| Ok(read_to_string(p)?));
+        let missing = read(Path::new("test_passstore_none"), |_| Ok(String::new()));
+        remove_dir_all(dir).unwrap();
+
+        let (entries, notes) = result.unwrap();
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["email/work/me", "github"]);
+        assert_eq!(entries[0].username.as_deref(), Some("me"));
+        assert_eq!(
+            notes,
+            vec!["email/empty has no password and was not imported"]
+        );
+        assert!(missing.is_err());
+    }
+}
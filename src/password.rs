@@ -0,0 +1,225 @@
+use std::{
+    env,
+    fs::{metadata, read_to_string, File},
+    io::Read,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use crate::args::Args;
+use crate::errors::{
+    Result,
+    SrpkError::{FlagInvalid, PasswordInput},
+};
+
+/// Environment variable holding a command that prints the vault password.
+pub const COMMAND_ENV: &str = "SRPK_PASSWORD_COMMAND";
+
+/// Where to read a password from instead of the terminal.
+pub struct Source<'a> {
+    /// Flag naming a file descriptor to read from.
+    pub fd_flag: &'a str,
+    /// Flag naming a file to read from.
+    pub file_flag: &'a str,
+    /// Environment variable holding a command to run, if commands are allowed.
+    pub command_env: Option<&'a str>,
+}
+
+/// Vault password: `--password-fd`, `--password-file`, or `SRPK_PASSWORD_COMMAND`.
+pub const VAULT: Source = Source {
+    fd_flag: "--password-fd",
+    file_flag: "--password-file",
+    command_env: Some(COMMAND_ENV),
+};
+
+/// New passwords stored with `mk`: `--new-password-fd` or `--new-password-file`.
+pub const NEW: Source = Source {
+    fd_flag: "--new-password-fd",
+    file_flag: "--new-password-file",
+    command_env: None,
+};
+
+/// Read a password from `source`, in the order listed there.
+///
+/// Returns `None` if none of them were given, so the caller should prompt instead.
+pub fn read_from(args: &Args, source: &Source) -> Result<Option<String>> {
+    if let Some(fd) = args.value_parse::<i32>(source.fd_flag)? {
+        return read_fd(fd, source.fd_flag).map(Some);
+    }
+    if args.flag(source.file_flag) {
+        let path: &String = args
+            .value(source.file_flag)
+            .ok_or_else(|| FlagInvalid(source.file_flag.to_owned()))?;
+        return read_file(Path::new(path)).map(Some);
+    }
+    if let Some(command) = source.command_env.and_then(env::var_os) {
+        return run_command(&command.to_string_lossy()).map(Some);
+    }
+    Ok(None)
+}
+
+/// Take the first line of `text`, which is the password; anything after it is ignored.
+fn first_line(text: &str) -> Result<String> {
+    match text.lines().next() {
+        Some(line) if !line.is_empty() => Ok(line.to_owned()),
+        _ => Err(PasswordInput("password is empty".to_owned())),
+    }
+}
+
+/// Passwords already read from each file descriptor, since a descriptor can only be read once
+/// but a command may need the vault password more than once.
+#[cfg(unix)]
+static FD_PASSWORDS: std::sync::Mutex<Vec<(i32, String)>> = std::sync::Mutex::new(Vec::new());
+
+/// Read the password from file descriptor `fd`, given with `flag`.
+#[cfg(unix)]
+fn read_fd(fd: i32, flag: &str) -> Result<String> {
+    use std::{mem::ManuallyDrop, os::fd::FromRawFd};
+
+    let mut cache = FD_PASSWORDS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((_, password)) = cache.iter().find(|(f, _)| *f == fd) {
+        return Ok(password.clone());
+    }
+    if fd < 0 {
+        return Err(FlagInvalid(flag.to_owned()));
+    }
+    if fd <= 2 && unsafe { libc::isatty(fd) } == 1 {
+        eprintln!(
+            "WARNING: file descriptor {} is a terminal, the password will be shown as you type",
+            fd
+        );
+    }
+    // leave the descriptor open, so its' number is not reused for another file while cached
+    let mut file: ManuallyDrop<File> = ManuallyDrop::new(unsafe { File::from_raw_fd(fd) });
+    let mut text: String = String::new();
+    file.read_to_string(&mut text)
+        .map_err(|e| PasswordInput(format!("file descriptor {}: {}", fd, e)))?;
+    let password: String = first_line(&text)?;
+    cache.push((fd, password.clone()));
+    Ok(password)
+}
+
+#[cfg(not(unix))]
+fn read_fd(_fd: i32, _flag: &str) -> Result<String> {
+    Err(PasswordInput(
+        "file descriptors are only supported on unix".to_owned(),
+    ))
+}
+
+/// Whether a file with permissions `mode` can be read by anyone but its' owner.
+#[cfg(unix)]
+fn mode_insecure(mode: u32) -> bool {
+    mode & 0o077 != 0
+}
+
+fn read_file(path: &Path) -> Result<String> {
+    let meta = metadata(path).map_err(|e| PasswordInput(format!("{}: {}", path.display(), e)))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        if mode_insecure(meta.permissions().mode()) {
+            eprintln!(
+                "WARNING: {} can be read by other users; run `chmod 600 {}`",
+                path.display(),
+                path.display()
+            );
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = meta;
+
+    let text: String =
+        read_to_string(path).map_err(|e| PasswordInput(format!("{}: {}", path.display(), e)))?;
+    first_line(&text)
+}
+
+fn run_command(command: &str) -> Result<String> {
+    #[cfg(unix)]
+    let mut shell: Command = Command::new("sh");
+    #[cfg(unix)]
+    shell.arg("-c");
+    #[cfg(not(unix))]
+    let mut shell: Command = Command::new("cmd");
+    #[cfg(not(unix))]
+    shell.arg("/C");
+
+    let output = shell
+        .arg(command)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| PasswordInput(format!("{}: {}", COMMAND_ENV, e)))?;
+    if !output.status.success() {
+        return Err(PasswordInput(format!(
+            "{} exited with {}",
+            COMMAND_ENV, output.status
+        )));
+    }
+    let text: String = String::from_utf8(output.stdout)?;
+    first_line(&text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Args {
+        Args::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn test_first_line() {
+        assert_eq!(first_line("pass\n").unwrap(), "pass");
+        assert_eq!(first_line("pass\r\nother\n").unwrap(), "pass");
+        assert!(first_line("").is_err());
+        assert!(first_line("\nother").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        std::fs::create_dir("password_test_read_file").unwrap();
+        let path: &str = "./password_test_read_file/pass";
+        std::fs::write(path, "hunter2\n").unwrap();
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600)).unwrap();
+
+        let args: Args = parse(&["ls", "--new-password-file", path]);
+        assert_eq!(read_from(&args, &NEW).unwrap().unwrap(), "hunter2");
+        assert!(read_from(&args, &VAULT).unwrap().is_none());
+        assert!(read_from(&parse(&["ls", "--new-password-file"]), &NEW).is_err());
+        assert!(matches!(
+            read_from(&parse(&["ls", "--new-password-fd", "-1"]), &NEW),
+            Err(FlagInvalid(flag)) if flag == "--new-password-fd"
+        ));
+        assert!(mode_insecure(0o644));
+        assert!(!mode_insecure(0o600));
+        std::fs::remove_dir_all("password_test_read_file").unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_fd_twice() {
+        use std::{io::Write, os::fd::FromRawFd};
+
+        let mut fds: [i32; 2] = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let mut writer: File = unsafe { File::from_raw_fd(fds[1]) };
+        writer.write_all(b"hunter2\n").unwrap();
+        drop(writer);
+
+        let args: Args = parse(&["ls", "--password-fd", &fds[0].to_string()]);
+        assert_eq!(read_from(&args, &VAULT).unwrap().unwrap(), "hunter2");
+        assert_eq!(read_from(&args, &VAULT).unwrap().unwrap(), "hunter2");
+        unsafe { libc::close(fds[0]) };
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_command() {
+        assert_eq!(run_command("printf 'hunter2\\nextra'").unwrap(), "hunter2");
+        assert!(run_command("exit 3").is_err());
+    }
+}
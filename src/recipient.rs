@@ -0,0 +1,94 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use std::{
+    fs::{read, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+use crate::errors::{
+    Result,
+    SrpkError::{PathTaken, RecipientInvalid},
+};
+
+const PUBLIC_PREFIX: &str = "srpk1";
+const SECRET_PREFIX: &str = "SRPK-SECRET-KEY-1";
+
+fn decode(text: &str, prefix: &str) -> Result<[u8; 32]> {
+    let invalid = || RecipientInvalid(text.to_owned());
+    let encoded: &str = text.strip_prefix(prefix).ok_or_else(invalid)?;
+    let bytes: Vec<u8> = URL_SAFE_NO_PAD.decode(encoded).map_err(|_| invalid())?;
+    bytes.try_into().map_err(|_| invalid())
+}
+
+/// Turn an X25519 public key into the form users share with each other.
+pub fn encode_public(public: &[u8; 32]) -> String {
+    format!("{}{}", PUBLIC_PREFIX, URL_SAFE_NO_PAD.encode(public))
+}
+
+/// Read a public key produced by `encode_public`.
+pub fn decode_public(text: &str) -> Result<[u8; 32]> {
+    decode(text.trim(), PUBLIC_PREFIX)
+}
+
+/// Read the X25519 secret from the identity file at `path`.
+///
+/// Blank lines and lines starting with `#` are ignored.
+pub fn read_identity(path: &Path) -> Result<[u8; 32]> {
+    let file: String = String::from_utf8(read(path)?)?;
+    let line: &str = file
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty() && !l.starts_with('#'))
+        .ok_or_else(|| RecipientInvalid(path.display().to_string()))?;
+    decode(line, SECRET_PREFIX)
+}
+
+/// Write the X25519 `secret` to a new identity file at `path`, readable only by the owner.
+///
+/// Returns `Err(PathTaken)` if `path` already exists.
+pub fn write_identity(path: &Path, secret: &[u8; 32], public: &[u8; 32]) -> Result<()> {
+    if path.exists() {
+        return Err(PathTaken(path.to_path_buf()));
+    }
+
+    let mut options: OpenOptions = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    writeln!(file, "# public key: {}", encode_public(public))?;
+    writeln!(file, "{}{}", SECRET_PREFIX, URL_SAFE_NO_PAD.encode(secret))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypt::x25519_generate;
+
+    #[test]
+    fn test_public_roundtrip() {
+        let (_, public) = x25519_generate();
+        assert_eq!(decode_public(&encode_public(&public)).unwrap(), public);
+    }
+
+    #[test]
+    fn test_public_invalid() {
+        assert!(decode_public("srpk1notakey").is_err());
+        assert!(decode_public("age1qqqq").is_err());
+    }
+
+    #[test]
+    fn test_identity_roundtrip() {
+        std::fs::create_dir("recipient_test_identity").unwrap();
+        let path: &Path = Path::new("./recipient_test_identity/me.key");
+        let (secret, public) = x25519_generate();
+        write_identity(path, &secret, &public).unwrap();
+        assert!(write_identity(path, &secret, &public).is_err());
+        assert_eq!(read_identity(path).unwrap(), secret);
+        std::fs::remove_dir_all("recipient_test_identity").unwrap();
+    }
+}
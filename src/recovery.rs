@@ -0,0 +1,154 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use sha2::{Digest, Sha256};
+use sharks::{Share, Sharks};
+
+use crate::errors::{
+    Result,
+    SrpkError::{ShareInvalid, ShareMismatch, ShareThreshold},
+};
+
+const SHARE_PREFIX: &str = "srpk-share-";
+
+fn checksum(domain: &[u8], bytes: &[u8]) -> [u8; 4] {
+    let mut hasher = Sha256::new();
+    hasher.update(domain);
+    hasher.update(bytes);
+    hasher.finalize()[..4].try_into().unwrap()
+}
+
+/// Split the data `key` of a vault into `shares` printable shares,
+/// any `threshold` of which can be combined to recover it.
+///
+/// Each share is laid out as `threshold | key fingerprint | share | checksum`,
+/// so that typos and shares of a different key are caught before combining.
+pub fn split(key: &[u8; 32], threshold: u8, shares: u8) -> Result<Vec<String>> {
+    if threshold < 2 || shares < threshold {
+        return Err(ShareThreshold);
+    }
+
+    let fingerprint: [u8; 4] = checksum(b"srpk-key", key);
+    Ok(Sharks(threshold)
+        .dealer(key)
+        .take(shares as usize)
+        .map(|share| {
+            let mut payload: Vec<u8> = vec![threshold];
+            payload.extend_from_slice(&fingerprint);
+            payload.extend(Vec::from(&share));
+            payload.extend_from_slice(&checksum(b"srpk-share", &payload));
+            format!("{}{}", SHARE_PREFIX, URL_SAFE_NO_PAD.encode(payload))
+        })
+        .collect())
+}
+
+/// Collects printed shares until enough are present to recover a key.
+#[derive(Default)]
+pub struct Combiner {
+    threshold: u8,
+    fingerprint: [u8; 4],
+    shares: Vec<Share>,
+}
+
+impl Combiner {
+    /// Check the integrity of printed share `text` and add it.
+    ///
+    /// Returns `Err(ShareInvalid)` if the share is damaged,
+    /// belongs to a different key than earlier shares, or was already added.
+    pub fn add(&mut self, text: &str) -> Result<()> {
+        let text: &str = text.trim();
+        let invalid = |reason: &str| ShareInvalid(reason.to_owned());
+        let payload: Vec<u8> = text
+            .strip_prefix(SHARE_PREFIX)
+            .and_then(|encoded| URL_SAFE_NO_PAD.decode(encoded).ok())
+            .ok_or_else(|| invalid("not a srpk share"))?;
+        if payload.len() != 1 + 4 + 33 + 4 {
+            return Err(invalid("wrong length"));
+        }
+        let (body, sum) = payload.split_at(payload.len() - 4);
+        if checksum(b"srpk-share", body) != sum {
+            return Err(invalid("checksum mismatch (typo?)"));
+        }
+
+        let threshold: u8 = body[0];
+        let fingerprint: [u8; 4] = body[1..5].try_into().unwrap();
+        let share: Share = Share::try_from(&body[5..]).map_err(invalid)?;
+        if self.shares.is_empty() {
+            self.threshold = threshold;
+            self.fingerprint = fingerprint;
+        } else if threshold != self.threshold || fingerprint != self.fingerprint {
+            return Err(invalid("share belongs to a different key"));
+        }
+        if self.shares.iter().any(|s| s.x.0 == share.x.0) {
+            return Err(invalid("share was already entered"));
+        }
+
+        self.shares.push(share);
+        Ok(())
+    }
+
+    /// How many more shares are needed, or `None` if no shares have been added yet.
+    pub fn remaining(&self) -> Option<usize> {
+        if self.shares.is_empty() {
+            return None;
+        }
+        Some((self.threshold as usize).saturating_sub(self.shares.len()))
+    }
+
+    /// Recover the key from the added shares.
+    ///
+    /// Returns `Err(ShareMismatch)` if the result does not match the key the shares were made from.
+    pub fn combine(&self) -> Result<[u8; 32]> {
+        let key: Vec<u8> = Sharks(self.threshold)
+            .recover(&self.shares)
+            .map_err(|e| ShareInvalid(e.to_owned()))?;
+        let key: [u8; 32] = key.try_into().map_err(|_| ShareMismatch)?;
+        if checksum(b"srpk-key", &key) != self.fingerprint {
+            return Err(ShareMismatch);
+        }
+        Ok(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypt::generate_key;
+
+    #[test]
+    fn test_split_combine() {
+        let key: [u8; 32] = generate_key();
+        let shares: Vec<String> = split(&key, 3, 5).unwrap();
+        assert_eq!(shares.len(), 5);
+
+        let mut combiner: Combiner = Combiner::default();
+        assert_eq!(combiner.remaining(), None);
+        combiner.add(&shares[4]).unwrap();
+        combiner.add(&shares[0]).unwrap();
+        assert_eq!(combiner.remaining(), Some(1));
+        assert!(combiner.combine().is_err());
+        combiner.add(&shares[2]).unwrap();
+        assert_eq!(combiner.remaining(), Some(0));
+        assert_eq!(combiner.combine().unwrap(), key);
+    }
+
+    #[test]
+    fn test_share_integrity() {
+        let shares: Vec<String> = split(&generate_key(), 2, 3).unwrap();
+        let other: Vec<String> = split(&generate_key(), 2, 3).unwrap();
+
+        let mut combiner: Combiner = Combiner::default();
+        let mut typo: Vec<u8> = shares[0].clone().into_bytes();
+        let last: usize = typo.len() - 5;
+        typo[last] = if typo[last] == b'A' { b'B' } else { b'A' };
+        assert!(combiner.add(&String::from_utf8(typo).unwrap()).is_err());
+
+        combiner.add(&shares[0]).unwrap();
+        assert!(combiner.add(&shares[0]).is_err());
+        assert!(combiner.add(&other[1]).is_err());
+    }
+
+    #[test]
+    fn test_split_bad_threshold() {
+        assert!(split(&generate_key(), 1, 5).is_err());
+        assert!(split(&generate_key(), 4, 3).is_err());
+    }
+}
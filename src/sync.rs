@@ -0,0 +1,274 @@
+use std::{
+    fs::create_dir_all,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use crate::errors::{
+    Result,
+    SrpkError::{NoSync, RunFailed},
+};
+
+/// Branch the vault is committed to, here and on the remote.
+const BRANCH: &str = "main";
+
+/// Name of the remote the vault is synced with.
+const REMOTE: &str = "origin";
+
+/// How the vault's history compares with the remote's.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Status {
+    /// The remote has the same commits.
+    UpToDate,
+    /// Only this copy has new commits, or the remote has none yet.
+    Ahead,
+    /// Only the remote has new commits.
+    Behind,
+    /// Both have new commits.
+    Diverged,
+}
+
+/// The git repository keeping the history of one vault file.
+///
+/// The repository lives next to the vault as `<vault>.git`, and only ever tracks the vault,
+/// so it does not get in the way of any other repository its' directory is in.
+pub struct Repo {
+    git_dir: PathBuf,
+    work_tree: PathBuf,
+    file: String,
+}
+
+impl Repo {
+    /// Get the repository of the vault at `path`, which may not exist yet.
+    pub fn of(path: &str) -> Self {
+        let path: PathBuf = std::path::absolute(path).unwrap_or_else(|_| PathBuf::from(path));
+        let mut git_dir: PathBuf = path.clone();
+        git_dir.as_mut_os_string().push(".git");
+        Self {
+            git_dir,
+            work_tree: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            file: path
+                .file_name()
+                .map(|f| f.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Whether `srpk sync init` has been run for this vault.
+    pub fn exists(&self) -> bool {
+        self.git_dir.is_dir()
+    }
+
+    /// Run git on this repository, returning what it printed.
+    ///
+    /// Returns `Err(RunFailed)` with git's complaint if it fails.
+    fn git(&self, args: &[&str]) -> Result<String> {
+        git(Command::new("git")
+            .arg("-C")
+            .arg(&self.work_tree)
+            .arg("--git-dir")
+            .arg(&self.git_dir)
+            .args(args))
+    }
+
+    /// Whether git `args` succeed, for commands that answer with their exit code.
+    fn check(&self, args: &[&str]) -> Result<bool> {
+        Ok(Command::new("git")
+            .arg("-C")
+            .arg(&self.work_tree)
+            .arg("--git-dir")
+            .arg(&self.git_dir)
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map_err(|e| RunFailed("git".to_owned(), e.to_string()))?
+            .success())
+    }
+
+    /// Create the repository, syncing with `remote`, and commit the vault.
+    ///
+    /// `remote` can be any git remote; a path that does not exist yet becomes a new bare repository.
+    pub fn init(&self, remote: &str) -> Result<()> {
+        // git resolves paths from inside the repository, so make them absolute first
+        let is_url: bool = remote.contains("://") || remote.contains('@');
+        let remote: String = match is_url {
+            true => remote.to_owned(),
+            false => std::path::absolute(remote)?.to_string_lossy().into_owned(),
+        };
+        let remote: &str = &remote;
+        if !is_url && !Path::new(remote).exists() {
+            create_dir_all(remote)?;
+            git(Command::new("git").args(["init", "--quiet", "--bare", remote]))?;
+            git(Command::new("git").args([
+                "--git-dir",
+                remote,
+                "symbolic-ref",
+                "HEAD",
+                &format!("refs/heads/{}", BRANCH),
+            ]))?;
+        }
+
+        let git_dir: &str = &self.git_dir.to_string_lossy();
+        git(Command::new("git").args(["init", "--quiet", "--bare", git_dir]))?;
+        self.git(&["config", "core.bare", "false"])?;
+        self.git(&["symbolic-ref", "HEAD", &format!("refs/heads/{}", BRANCH)])?;
+        if self.git(&["config", "user.email"]).is_err() {
+            self.git(&["config", "user.name", "srpk"])?;
+            self.git(&["config", "user.email", "srpk@localhost"])?;
+        }
+        self.git(&["remote", "add", REMOTE, remote])?;
+        self.commit("srpk sync init")?;
+        Ok(())
+    }
+
+    /// Commit the vault with `message` if it changed since the last commit.
+    ///
+    /// Returns whether anything was committed.
+    pub fn commit(&self, message: &str) -> Result<bool> {
+        self.git(&["add", "--", &self.file])?;
+        if self.check(&["diff", "--cached", "--quiet"])? {
+            return Ok(false);
+        }
+        self.git(&["commit", "--quiet", "-m", message])?;
+        Ok(true)
+    }
+
+    fn remote_branch(&self) -> String {
+        format!("{}/{}", REMOTE, BRANCH)
+    }
+
+    /// Fetch the remote, and compare the vault's history with it.
+    pub fn fetch(&self) -> Result<Status> {
+        self.git(&["fetch", "--quiet", REMOTE])?;
+        let remote: &str = &self.remote_branch();
+        if !self.check(&["rev-parse", "--verify", "--quiet", remote])? {
+            return Ok(Status::Ahead);
+        }
+        let ours: String = self.git(&["rev-parse", "HEAD"])?;
+        let theirs: String = self.git(&["rev-parse", remote])?;
+        if ours == theirs {
+            Ok(Status::UpToDate)
+        } else if self.check(&["merge-base", "--is-ancestor", remote, "HEAD"])? {
+            Ok(Status::Ahead)
+        } else if self.check(&["merge-base", "--is-ancestor", "HEAD", remote])? {
+            Ok(Status::Behind)
+        } else {
+            Ok(Status::Diverged)
+        }
+    }
+
+    /// Get the vault file as it is on the remote.
+    pub fn remote_vault(&self) -> Result<Vec<u8>> {
+        let output = Command::new("git")
+            .arg("--git-dir")
+            .arg(&self.git_dir)
+            .args(["show", &format!("{}:{}", self.remote_branch(), self.file)])
+            .output()
+            .map_err(|e| RunFailed("git".to_owned(), e.to_string()))?;
+        if !output.status.success() {
+            let stderr: String = String::from_utf8_lossy(&output.stderr).trim().to_owned();
+            return Err(RunFailed("git".to_owned(), stderr));
+        }
+        Ok(output.stdout)
+    }
+
+    /// Move to the remote's history, when only it has new commits.
+    pub fn fast_forward(&self) -> Result<()> {
+        self.git(&["merge", "--quiet", "--ff-only", &self.remote_branch()])?;
+        Ok(())
+    }
+
+    /// Commit the vault, as merged by srpk, with both this copy's and the remote's history behind it.
+    pub fn commit_merge(&self, message: &str) -> Result<()> {
+        self.git(&["add", "--", &self.file])?;
+        let tree: String = self.git(&["write-tree"])?;
+        let commit: String = self.git(&[
+            "commit-tree",
+            &tree,
+            "-p",
+            "HEAD",
+            "-p",
+            &self.remote_branch(),
+            "-m",
+            message,
+        ])?;
+        self.git(&["update-ref", "HEAD", &commit])?;
+        Ok(())
+    }
+
+    /// Send this copy's commits to the remote.
+    pub fn push(&self) -> Result<()> {
+        self.git(&["push", "--quiet", REMOTE, &format!("HEAD:{}", BRANCH)])?;
+        Ok(())
+    }
+}
+
+/// Run `command`, returning what it printed.
+fn git(command: &mut Command) -> Result<String> {
+    let output = command
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| RunFailed("git".to_owned(), e.to_string()))?;
+    if !output.status.success() {
+        let stderr: String = String::from_utf8_lossy(&output.stderr).trim().to_owned();
+        return Err(RunFailed("git".to_owned(), stderr));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+/// Get the repository of the vault at `path`.
+///
+/// Returns `Err(NoSync)` if `srpk sync init` has not been run for it.
+pub fn repo(path: &str) -> Result<Repo> {
+    let repo: Repo = Repo::of(path);
+    match repo.exists() {
+        true => Ok(repo),
+        false => Err(NoSync),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir, remove_dir_all, write};
+
+    #[test]
+    fn test_repo() {
+        let dir: &str = "test_sync_repo";
+        create_dir(dir).unwrap();
+        create_dir(format!("{}/a", dir)).unwrap();
+        create_dir(format!("{}/b", dir)).unwrap();
+        let remote: String = format!("{}/remote.git", dir);
+        let a: Repo = Repo::of(&format!("{}/a/v.db", dir));
+        let b: Repo = Repo::of(&format!("{}/b/v.db", dir));
+
+        let result = (|| -> Result<()> {
+            assert!(!a.exists());
+            write(format!("{}/a/v.db", dir), "one")?;
+            a.init(&remote)?;
+            assert!(!a.commit("unchanged")?);
+            assert_eq!(a.fetch()?, Status::Ahead);
+            a.push()?;
+            assert_eq!(a.fetch()?, Status::UpToDate);
+
+            write(format!("{}/b/v.db", dir), "other")?;
+            b.init(&remote)?;
+            assert_eq!(b.fetch()?, Status::Diverged);
+            assert_eq!(b.remote_vault()?, b"one");
+            b.commit_merge("merge")?;
+            b.push()?;
+
+            assert_eq!(a.fetch()?, Status::Behind);
+            a.fast_forward()?;
+            assert_eq!(std::fs::read(format!("{}/a/v.db", dir))?, b"other");
+            write(format!("{}/a/v.db", dir), "two")?;
+            assert!(a.commit("change")?);
+            assert_eq!(a.fetch()?, Status::Ahead);
+            Ok(())
+        })();
+        remove_dir_all(dir).unwrap();
+        result.unwrap();
+        assert!(repo("test_sync_none/v.db").is_err());
+    }
+}
@@ -0,0 +1,143 @@
+use crate::errors::{Result, SrpkError::TemplateInvalid};
+
+/// Opens a reference to the vault in a template, e.g. `{{ srpk "prod/db" }}`.
+const OPEN: &str = "{{";
+const CLOSE: &str = "}}";
+const NAME: &str = "srpk";
+
+/// A reference to the vault: password `key`, or its' metadata `field` if given.
+#[derive(Debug, PartialEq)]
+pub struct Reference {
+    pub key: String,
+    pub field: Option<String>,
+}
+
+/// Split the inside of `{{ ... }}` into its' words, which may be quoted with `"`.
+///
+/// Inside quotes, `\"` and `\\` stand for `"` and `\`.
+fn words(inner: &str) -> core::result::Result<Vec<String>, String> {
+    let mut words: Vec<String> = Vec::new();
+    let mut chars = inner.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        let mut word: String = String::new();
+        if c != '"' {
+            word.push(c);
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                word.push(c);
+            }
+            words.push(word);
+            continue;
+        }
+        loop {
+            match chars.next() {
+                Some('"') => break,
+                Some('\\') => match chars.next() {
+                    Some(c @ ('"' | '\\')) => word.push(c),
+                    _ => return Err("unknown escape in quotes".to_owned()),
+                },
+                Some(c) => word.push(c),
+                None => return Err("unclosed quote".to_owned()),
+            }
+        }
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// Read the reference inside `{{ ... }}`.
+///
+/// Returns `None` if it is not meant for srpk, so it is left alone for other tools.
+fn reference(inner: &str) -> core::result::Result<Option<Reference>, String> {
+    let words: Vec<String> = words(inner)?;
+    match words.as_slice() {
+        [name, ..] if name != NAME => Ok(None),
+        [_, key] => Ok(Some(Reference {
+            key: key.to_owned(),
+            field: None,
+        })),
+        [_, key, field] => Ok(Some(Reference {
+            key: key.to_owned(),
+            field: Some(field.to_owned()),
+        })),
+        _ => Err("expected {{ srpk \"key\" }} or {{ srpk \"key\" \"field\" }}".to_owned()),
+    }
+}
+
+/// Replace each reference in `template` with what `lookup` gives for it.
+///
+/// Returns `Err(TemplateInvalid)` with the line number of a reference that cannot be read,
+/// or the first error from `lookup`.
+pub fn render<F>(template: &str, mut lookup: F) -> Result<String>
+where
+    F: FnMut(&Reference) -> Result<String>,
+{
+    let mut output: String = String::with_capacity(template.len());
+    let mut rest: &str = template;
+    while let Some(start) = rest.find(OPEN) {
+        let line: usize = template[..template.len() - rest.len() + start]
+            .matches('\n')
+            .count()
+            + 1;
+        output.push_str(&rest[..start]);
+        let after: &str = &rest[start + OPEN.len()..];
+        let Some(end) = after.find(CLOSE) else {
+            return Err(TemplateInvalid(line, format!("no closing {}", CLOSE)));
+        };
+        match reference(&after[..end]).map_err(|e| TemplateInvalid(line, e))? {
+            Some(reference) => output.push_str(&lookup(&reference)?),
+            None => output.push_str(&rest[start..start + OPEN.len() + end + CLOSE.len()]),
+        }
+        rest = &after[end + CLOSE.len()..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::SrpkError::KeyNonExist;
+
+    fn lookup(reference: &Reference) -> Result<String> {
+        match (reference.key.as_str(), reference.field.as_deref()) {
+            ("prod/db", None) => Ok("hunter2".to_owned()),
+            ("prod/db", Some("username")) => Ok("admin".to_owned()),
+            (key, _) => Err(KeyNonExist(key.to_owned())),
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let template: &str = "user = \"{{ srpk \"prod/db\" \"username\" }}\"\n\
+                              pass = \"{{srpk \"prod/db\"}}\"\n\
+                              other = \"{{ .Values.other }}\"\n";
+        assert_eq!(
+            render(template, lookup).unwrap(),
+            "user = \"admin\"\npass = \"hunter2\"\nother = \"{{ .Values.other }}\"\n"
+        );
+        assert_eq!(render("no references", lookup).unwrap(), "no references");
+    }
+
+    #[test]
+    fn test_render_invalid() {
+        assert!(matches!(
+            render("a\n{{ srpk \"missing\" }}", lookup),
+            Err(KeyNonExist(_))
+        ));
+        assert!(matches!(
+            render("a\nb {{ srpk \"prod/db\" ", lookup),
+            Err(TemplateInvalid(2, _))
+        ));
+        assert!(matches!(
+            render("{{ srpk \"prod/db }}", lookup),
+            Err(TemplateInvalid(1, _))
+        ));
+        assert!(matches!(
+            render("{{ srpk }}", lookup),
+            Err(TemplateInvalid(1, _))
+        ));
+    }
+}
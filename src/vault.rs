@@ -1,22 +1,120 @@
 use sqlite::{Connection, State, Value};
 use std::{
-    fs::{read, remove_file, write},
+    fs::{metadata, read, remove_file, write, File, OpenOptions, TryLockError},
+    io::Write,
+    mem::ManuallyDrop,
     path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
-use crate::crypt::{aes256_decrypt, aes256_encrypt, CryptValue};
+use crate::audit::{self, Event};
+use crate::crypt::{
+    aes256_decrypt, aes256_encrypt, aes256_open_aad, aes256_seal_aad, generate_key, x25519_decrypt,
+    x25519_encrypt, x25519_public, CryptValue,
+};
 use crate::errors::{
     Result,
-    SrpkError::{KeyDuplicate, KeyNonExist},
+    SrpkError::{
+        IdentityNoSlot, KeyDuplicate, KeyNonExist, RecipientDuplicate, RecipientNonExist,
+        SchemaNewer, SlotsFull, TempStale, TrashNonExist, VaultInUse, VaultMalformed, AES256,
+    },
 };
+use crate::header::{Header, Slot, MAX_SLOTS};
+use crate::oplog::{self, Op};
+use crate::recipient::encode_public;
 
-const PASSWORD_NEW_SQL: &str = "INSERT INTO srpk VALUES (:key, :pass);";
+const PASSWORD_NEW_SQL: &str =
+    "INSERT INTO srpk (key, value, modified) VALUES (:key, :pass, :modified);";
 const PASSWORD_GET_SQL: &str = "SELECT value FROM srpk WHERE key = ?;";
 const PASSWORD_DEL_SQL: &str = "DELETE FROM srpk WHERE key = ?";
 const PASSWORD_LS_SQL: &str = "SELECT key FROM srpk;";
+const PASSWORD_DUPLICATES_SQL: &str = "SELECT key FROM srpk GROUP BY key HAVING COUNT(*) > 1;";
+const META_SET_SQL: &str = "INSERT INTO srpk_meta VALUES (:key, :field, :value);";
+const META_GET_SQL: &str = "SELECT value FROM srpk_meta WHERE key = ? AND field = ?;";
+const META_DEL_SQL: &str = "DELETE FROM srpk_meta WHERE key = ? AND field = ?;";
+const META_DEL_ALL_SQL: &str = "DELETE FROM srpk_meta WHERE key = ?;";
+const META_LS_SQL: &str = "SELECT field, value FROM srpk_meta WHERE key = ? ORDER BY field;";
+const MODIFIED_GET_SQL: &str = "SELECT modified FROM srpk WHERE key = ?;";
+const MODIFIED_SET_SQL: &str = "UPDATE srpk SET modified = ? WHERE key = ?;";
+const STATE_GET_SQL: &str = "SELECT value FROM srpk_state WHERE name = ?;";
+const STATE_SET_SQL: &str = "INSERT OR REPLACE INTO srpk_state VALUES (:name, :value);";
+const OPLOG_SET_SQL: &str = "INSERT OR REPLACE INTO srpk_oplog (key, clock, device, deleted)
+    VALUES (:key, :clock, :device, :deleted);";
+const OPLOG_LS_SQL: &str = "SELECT o.key, o.clock, o.device, o.deleted, s.value
+    FROM srpk_oplog o LEFT JOIN srpk s ON s.key = o.key ORDER BY o.key;";
+const OPLOG_UNLOGGED_SQL: &str =
+    "SELECT key FROM srpk WHERE key NOT IN (SELECT key FROM srpk_oplog);";
+const OPLOG_UNLOGGED_TRASH_SQL: &str = "SELECT key FROM srpk_trash
+    WHERE key NOT IN (SELECT key FROM srpk) AND key NOT IN (SELECT key FROM srpk_oplog);";
+const OPLOG_PURGE_SQL: &str = "DELETE FROM srpk_oplog WHERE deleted = 1
+    AND key IN (SELECT key FROM srpk_trash WHERE deleted < ?);";
+
+const TRASH_SET_SQL: &str =
+    "INSERT OR REPLACE INTO srpk_trash VALUES (:key, :value, :meta, :deleted);";
+const TRASH_GET_SQL: &str = "SELECT value, meta FROM srpk_trash WHERE key = ?;";
+const TRASH_DEL_SQL: &str = "DELETE FROM srpk_trash WHERE key = ?;";
+const TRASH_LS_SQL: &str = "SELECT key, deleted FROM srpk_trash ORDER BY deleted, key;";
+const TRASH_PURGE_SQL: &str = "DELETE FROM srpk_trash WHERE deleted < ?;";
+const AUDIT_ADD_SQL: &str = "INSERT INTO srpk_audit (time, host, user, action, key, nonce, hash)
+    VALUES (:time, :host, :user, :action, :key, :nonce, :hash);";
+const AUDIT_LS_SQL: &str =
+    "SELECT id, time, host, user, action, key, nonce, hash FROM srpk_audit ORDER BY id;";
+const AUDIT_REHASH_SQL: &str = "UPDATE srpk_audit SET hash = ? WHERE id = ?;";
+
+/// Whether this process has written changes to a vault.
+static WRITTEN: AtomicBool = AtomicBool::new(false);
+
+/// Name of the vault state holding the Lamport clock of the operation log.
+const CLOCK_STATE: &str = "clock";
+
+/// Name of the vault state set once changes are logged for `srpk sync --dir`.
+const OPLOG_STATE: &str = "oplog";
+
+/// Name of the vault state holding how many events the audit log has and the hash of the last one,
+/// so events cannot be dropped from its' end unnoticed.
+const AUDIT_HEAD_STATE: &str = "audit_head";
+
+/// Clock of the operation seeded for a key that was never logged, so any logged change wins over it.
+const SEED_CLOCK: u64 = 0;
+
+/// Clock of the operation seeded for a deleted key that was never logged,
+/// so the delete wins over a copy that only seeded the key.
+const SEED_DELETE_CLOCK: u64 = 1;
+
+/// Current version of the vault database schema.
+pub const SCHEMA_VERSION: usize = 8;
+
+/// SQL bringing the schema from version `i` to `i + 1`.
+///
+/// Vaults made before schema versions existed are version 0 and already have the `srpk` table.
+const MIGRATIONS: [&str; SCHEMA_VERSION] = [
+    "CREATE TABLE IF NOT EXISTS srpk (key TEXT, value TEXT);",
+    "CREATE TABLE srpk_meta (key TEXT, field TEXT, value TEXT);",
+    "ALTER TABLE srpk ADD COLUMN modified INTEGER NOT NULL DEFAULT 0;
+    CREATE TABLE srpk_state (name TEXT PRIMARY KEY, value TEXT);",
+    "CREATE TABLE srpk_oplog (key TEXT PRIMARY KEY, clock INTEGER, device TEXT, value TEXT, meta TEXT);",
+    "CREATE TABLE srpk_trash (key TEXT PRIMARY KEY, value TEXT, meta TEXT, deleted INTEGER);",
+    "CREATE TABLE srpk_audit (id INTEGER PRIMARY KEY, time INTEGER, host TEXT, user TEXT,
+        action TEXT, key TEXT, hash TEXT);
+    CREATE TRIGGER srpk_audit_update BEFORE UPDATE ON srpk_audit
+        BEGIN SELECT RAISE(ABORT, 'audit log is append-only'); END;
+    CREATE TRIGGER srpk_audit_delete BEFORE DELETE ON srpk_audit
+        BEGIN SELECT RAISE(ABORT, 'audit log is append-only'); END;",
+    "ALTER TABLE srpk_oplog ADD COLUMN deleted INTEGER NOT NULL DEFAULT 0;
+    UPDATE srpk_oplog SET deleted = value IS NULL, value = NULL, meta = NULL;",
+    "ALTER TABLE srpk_audit ADD COLUMN nonce TEXT NOT NULL DEFAULT '';
+    DROP TRIGGER srpk_audit_update;
+    CREATE TRIGGER srpk_audit_update BEFORE UPDATE OF time, host, user, action, key, nonce
+        ON srpk_audit BEGIN SELECT RAISE(ABORT, 'audit log is append-only'); END;
+    DELETE FROM srpk_state WHERE name = 'audit_chain';",
+];
 
 /// Represents an opened srpk vault.
 ///
+/// The database is encrypted with a random data key,
+/// which is in turn encrypted into one or more slots in the vault header.
+///
 /// Create a vault:
 /// ```
 /// Vault::create("./myvault.db", "mypassword", 12u8)?;
@@ -34,11 +132,14 @@ const PASSWORD_LS_SQL: &str = "SELECT key FROM srpk;";
 /// vault.close(true)?;
 /// ```
 pub struct Vault {
-    conn: Connection,
-    pass: String,
+    conn: ManuallyDrop<Connection>,
+    key: [u8; 32],
+    slots: Vec<Slot>,
     path: PathBuf,
     path_temp: PathBuf,
-    cost: u8,
+    /// The temporary database, locked while the vault is open; `None` once it is shredded.
+    temp: Option<File>,
+    device: Option<String>,
 }
 
 impl Vault {
@@ -56,13 +157,14 @@ impl Vault {
 
         // create the initial DB
         let connection = sqlite::open(&path)?;
-        connection.execute("CREATE TABLE srpk (key TEXT, value TEXT);")?;
+        migrate(&connection)?;
         drop(connection);
 
         // encrypt & overwrite
+        let key: [u8; 32] = generate_key();
+        let slots: Vec<Slot> = vec![Slot::Password(aes256_encrypt(&key, pass, cost)?)];
         let db_raw: Vec<u8> = read(&path)?;
-        let db_enc: Vec<u8> = aes256_encrypt(&db_raw, pass, cost)?;
-        write(&path, db_enc)?;
+        write(&path, seal_vault(&db_raw, &key, slots)?)?;
 
         Ok(())
     }
@@ -70,7 +172,10 @@ impl Vault {
     /// Open a vault at `path` using `pass`.
     ///
     /// Creates a temporary database for interfacing with at `path_temp`,
-    /// which will be removed when `Vault.close()` is called.
+    /// which will be shredded when `Vault.close()` is called.
+    ///
+    /// Legacy (password-only) vaults are given a data key and a password slot,
+    /// and are upgraded to the slotted format on `Vault.close(true)`.
     ///
     /// Example:
     /// ```
@@ -79,31 +184,142 @@ impl Vault {
     /// vault.close(false)?;
     /// ```
     pub fn open(path: &str, pass: &str) -> Result<Self> {
-        let mut path_str: String = path.to_owned();
-        let path: PathBuf = PathBuf::from(&path_str);
-        path_str.push_str(".temp");
-        let path_temp: PathBuf = PathBuf::from(&path_str);
+        Self::open_with(path, Unlock::Password(pass))
+    }
 
-        // decrypt
-        let db_enc: Vec<u8> = read(&path)?;
-        let db_raw: CryptValue = aes256_decrypt(&db_enc, pass)?;
+    /// Open a vault at `path` using the X25519 secret `identity`.
+    ///
+    /// Returns `Err(IdentityNoSlot)` if the vault has no slot for `identity`.
+    ///
+    /// Example:
+    /// ```
+    /// let identity: [u8; 32] = recipient::read_identity("./me.key")?;
+    /// let vault: Vault = Vault::open_identity("./myvault.db", &identity)?;
+    /// vault.close(false)?;
+    /// ```
+    pub fn open_identity(path: &str, identity: &[u8; 32]) -> Result<Self> {
+        Self::open_with(path, Unlock::Identity(identity))
+    }
 
-        // create temp and return a connection
-        write(&path_temp, db_raw.value)?;
+    /// Open a vault at `path` using its' raw data `key`.
+    ///
+    /// Used when the key was recovered by other means, e.g. `srpk recovery combine`.
+    pub fn open_key(path: &str, key: &[u8; 32]) -> Result<Self> {
+        Self::open_with(path, Unlock::Key(key))
+    }
+
+    /// Open a vault at `path`, unlocking it `with` any of the supported methods.
+    ///
+    /// Returns `Err(TempStale)` if a temporary database from an earlier session is still present;
+    /// see `Vault::temp_merge` and `Vault::temp_shred`.
+    pub fn open_with(path: &str, with: Unlock) -> Result<Self> {
+        Self::open_schema(path, with, true)
+    }
+
+    /// Open a vault at `path` like `Vault::open_with`, but leave its' schema as it was written
+    /// instead of bringing it up to `SCHEMA_VERSION`, even if it is newer.
+    ///
+    /// For inspecting a vault as it is, such as with `srpk verify`; close it without changes.
+    pub fn open_raw(path: &str, with: Unlock) -> Result<Self> {
+        Self::open_schema(path, with, false)
+    }
+
+    fn open_schema(path: &str, with: Unlock, migrating: bool) -> Result<Self> {
+        let path_temp: PathBuf = temp_path(path);
+        if path_temp.exists() {
+            return Err(match temp_locked(&path_temp) {
+                true => VaultInUse(path_temp),
+                false => TempStale(path_temp),
+            });
+        }
+
+        let (key, slots, db_raw) = unlock(&read(path)?, with)?;
+
+        // create temp, locked so other srpk processes know it is in use, and return a connection
+        let mut temp: File = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path_temp)?;
+        temp.try_lock().map_err(|_| VaultInUse(path_temp.clone()))?;
+        temp.write_all(&db_raw)?;
         let conn = sqlite::open(&path_temp)?;
+        // overwrite deleted rows, so old secrets are not left in free pages of the sealed database
+        let ready: Result<()> = conn
+            .execute("PRAGMA secure_delete = ON;")
+            .map_err(Into::into)
+            .and_then(|_| if migrating { migrate(&conn) } else { Ok(()) });
+        if let Err(e) = ready {
+            drop(conn);
+            shred(&path_temp)?;
+            return Err(e);
+        }
 
         Ok(Self {
-            conn,
-            pass: pass.to_owned(),
-            path,
+            conn: ManuallyDrop::new(conn),
+            key,
+            slots,
+            path: PathBuf::from(path),
             path_temp,
-            cost: db_raw.cost,
+            temp: Some(temp),
+            device: None,
         })
     }
 
+    /// Get the temporary database left behind by an interrupted session with the vault at `path`.
+    ///
+    /// Returns `None` if there is no such file, or if it belongs to a session still running.
+    pub fn temp_stale(path: &str) -> Option<PathBuf> {
+        let path_temp: PathBuf = temp_path(path);
+        (path_temp.exists() && !temp_locked(&path_temp)).then_some(path_temp)
+    }
+
+    /// Encrypt the temporary database left behind at `path` back into the vault, then shred it.
+    ///
+    /// The temporary database started as a copy of the vault,
+    /// so this keeps any changes that were made before the session was interrupted.
+    /// Returns `Err(VaultMalformed)` without touching either file if it is not a usable database.
+    ///
+    /// Example:
+    /// ```
+    /// if Vault::temp_stale("./myvault.db").is_some() {
+    ///     Vault::temp_merge("./myvault.db", Unlock::Password("mypassword"))?;
+    /// }
+    /// ```
+    pub fn temp_merge(path: &str, with: Unlock) -> Result<()> {
+        let (key, slots, _) = unlock(&read(path)?, with)?;
+        let path_temp: PathBuf = temp_path(path);
+
+        let conn = sqlite::open(&path_temp)?;
+        let version: usize = schema_version(&conn)?;
+        let tables: usize = {
+            let mut statement = conn.prepare(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'srpk';",
+            )?;
+            statement.next()?;
+            statement.read::<i64, _>(0)? as usize
+        };
+        drop(conn);
+        if version > SCHEMA_VERSION || tables != 1 {
+            return Err(VaultMalformed(format!(
+                "{} is not a srpk database",
+                path_temp.display()
+            )));
+        }
+
+        let db_raw: Vec<u8> = read(&path_temp)?;
+        write(path, seal_vault(&db_raw, &key, slots)?)?;
+        WRITTEN.store(true, Ordering::Relaxed);
+        shred(&path_temp)
+    }
+
+    /// Overwrite and delete the temporary database left behind at `path`, discarding its' changes.
+    pub fn temp_shred(path: &str) -> Result<()> {
+        shred(&temp_path(path))
+    }
+
     /// Close the vault, applying changes if `changed`.
     ///
-    /// Deletes the temporary database.
+    /// Shreds the temporary database.
     /// If `changed` is `true`, the contents of temporary DB will be encrypted,
     /// and the encrypted data will replace the original DB.
     ///
@@ -114,17 +330,121 @@ impl Vault {
     /// vault.key_new("github", "password123!")?;
     /// vault.close(true)?;
     /// ```
-    pub fn close(self, changed: bool) -> Result<()> {
+    pub fn close(mut self, changed: bool) -> Result<()> {
         if changed {
             let path: &Path = Path::new(&self.path);
             let db_raw: Vec<u8> = read(&self.path_temp)?;
-            let db_enc: Vec<u8> = aes256_encrypt(&db_raw, &self.pass, self.cost)?;
-            write(path, db_enc)?;
+            write(path, seal_vault(&db_raw, &self.key, self.slots.clone())?)?;
+            WRITTEN.store(true, Ordering::Relaxed);
         }
 
-        drop(self.conn);
-        remove_file(&self.path_temp)?;
-        Ok(())
+        self.release()
+    }
+
+    /// Close the connection and shred the temporary database, unless that was done already.
+    fn release(&mut self) -> Result<()> {
+        let Some(temp) = self.temp.take() else {
+            return Ok(());
+        };
+        // SAFETY: `temp` is only taken once, so the connection is dropped once and never used after
+        unsafe { ManuallyDrop::drop(&mut self.conn) };
+        let shredded: Result<()> = shred(&self.path_temp);
+        drop(temp);
+        shredded
+    }
+
+    /// Get the data key of the vault.
+    ///
+    /// Anyone holding this key can decrypt the vault until it is rekeyed.
+    pub fn data_key(&self) -> [u8; 32] {
+        self.key
+    }
+
+    /// Get the bcrypt cost of the first password slot, if the vault has one.
+    pub fn cost(&self) -> Option<u8> {
+        self.slots.iter().find_map(|slot| match slot {
+            Slot::Password(payload) => payload.first().copied(),
+            _ => None,
+        })
+    }
+
+    /// Replace the data key of the vault and set a new password `pass`.
+    ///
+    /// Any existing password slots are replaced and recipients are kept.
+    /// Recovery shares made before the rekey will no longer work.
+    ///
+    /// Example:
+    /// ```
+    /// let mut vault: Vault = Vault::open("./myvault.db", "mypassword")?;
+    /// vault.rekey("newpassword", 12u8)?;
+    /// vault.close(true)?;
+    /// ```
+    pub fn rekey(&mut self, pass: &str, cost: u8) -> Result<()> {
+        let key: [u8; 32] = generate_key();
+        let mut slots: Vec<Slot> = vec![Slot::Password(aes256_encrypt(&key, pass, cost)?)];
+        for recipient in self.recipient_ls() {
+            let payload: Vec<u8> = x25519_encrypt(&key, &recipient)?;
+            slots.push(Slot::X25519 { recipient, payload });
+        }
+
+        let old: [u8; 32] = self.audit_chain();
+        self.key = key;
+        self.slots = slots;
+        self.audit_rechain(&old)?;
+        self.audit("rekey", None)
+    }
+
+    /// Allow the holder of the X25519 secret for `recipient` to open the vault.
+    ///
+    /// Returns `Err(RecipientDuplicate)` if `recipient` can already open this vault.
+    ///
+    /// Example:
+    /// ```
+    /// let mut vault: Vault = Vault::open("./myvault.db", "mypassword")?;
+    /// vault.recipient_add(&recipient::decode_public("srpk1...")?)?;
+    /// vault.close(true)?;
+    /// ```
+    pub fn recipient_add(&mut self, recipient: &[u8; 32]) -> Result<()> {
+        if self.recipient_ls().contains(recipient) {
+            return Err(RecipientDuplicate(encode_public(recipient)));
+        }
+        if self.slots.len() >= MAX_SLOTS {
+            return Err(SlotsFull);
+        }
+
+        let payload: Vec<u8> = x25519_encrypt(&self.key, recipient)?;
+        self.slots.push(Slot::X25519 {
+            recipient: *recipient,
+            payload,
+        });
+        self.audit("recipient add", Some(&encode_public(recipient)))
+    }
+
+    /// Remove the slot for `recipient` from the vault.
+    ///
+    /// Returns `Err(RecipientNonExist)` if `recipient` cannot open this vault.
+    ///
+    /// Note that this does not change the data key;
+    /// a removed recipient who kept a copy of it can still read future versions of the vault.
+    pub fn recipient_del(&mut self, recipient: &[u8; 32]) -> Result<()> {
+        if !self.recipient_ls().contains(recipient) {
+            return Err(RecipientNonExist(encode_public(recipient)));
+        }
+
+        self.slots
+            .retain(|slot| !matches!(slot, Slot::X25519 { recipient: r, .. } if r == recipient));
+        self.audit("recipient rm", Some(&encode_public(recipient)))
+    }
+
+    /// Get the public keys of every recipient that can open the vault.
+    pub fn recipient_ls(&self) -> Vec<[u8; 32]> {
+        self.slots
+            .iter()
+            .filter_map(|slot| match slot {
+                Slot::X25519 { recipient, .. } => Some(*recipient),
+                _ => None,
+            })
+            .collect()
     }
 
     /// Create new password `key` of content `pass` in the vault.
@@ -143,8 +463,18 @@ impl Vault {
             return Err(KeyDuplicate(key.to_owned()));
         };
 
+        self.insert(key, pass)?;
+        self.audit("mk", Some(key))?;
+        self.log(key)
+    }
+
+    fn insert(&self, key: &str, pass: &str) -> Result<()> {
         let mut statement = self.conn.prepare(PASSWORD_NEW_SQL)?;
-        statement.bind_iter::<_, (_, Value)>([(":key", key.into()), (":pass", pass.into())])?;
+        statement.bind_iter::<_, (_, Value)>([
+            (":key", key.into()),
+            (":pass", pass.into()),
+            (":modified", (now() as i64).into()),
+        ])?;
         while let Ok(State::Row) = statement.next() {}
         Ok(())
     }
@@ -171,7 +501,7 @@ impl Vault {
         Ok(None)
     }
 
-    /// Delete password `key` from the vault.
+    /// Delete password `key` from the vault, moving it and its' metadata to the trash.
     ///
     /// Returns `Err(KeyNonExist)` if the key does not exist in this vault.
     ///
@@ -187,13 +517,500 @@ impl Vault {
         if self.key_get(key)?.is_none() {
             return Err(KeyNonExist(key.to_owned()));
         };
+        self.trash(key)?;
+        self.remove(key)?;
+        self.audit("rm", Some(key))?;
+        self.log(key)
+    }
+
+    /// Copy password `key` and its' metadata to the trash, replacing any older copy there.
+    fn trash(&self, key: &str) -> Result<()> {
+        let value: String = self.key_get(key)?.unwrap_or_default();
+        let meta: String = serde_json::to_string(&self.meta_ls(key)?)
+            .map_err(|e| VaultMalformed(format!("metadata of {}: {}", key, e)))?;
+        let mut statement = self.conn.prepare(TRASH_SET_SQL)?;
+        statement.bind_iter::<_, (_, Value)>([
+            (":key", key.into()),
+            (":value", value.into()),
+            (":meta", meta.into()),
+            (":deleted", (now() as i64).into()),
+        ])?;
+        while let Ok(State::Row) = statement.next() {}
+        Ok(())
+    }
+
+    /// Get the name of each key in the trash, and when it was deleted, oldest first.
+    pub fn trash_ls(&self) -> Result<Vec<(String, u64)>> {
+        let mut statement = self.conn.prepare(TRASH_LS_SQL)?;
+        let mut keys: Vec<(String, u64)> = Vec::new();
+        while let Ok(State::Row) = statement.next() {
+            keys.push((
+                statement.read::<String, _>("key")?,
+                statement.read::<i64, _>("deleted")? as u64,
+            ));
+        }
+        Ok(keys)
+    }
+
+    /// Move password `key` and its' metadata back from the trash.
+    ///
+    /// Returns `Err(TrashNonExist)` if the key is not in the trash,
+    /// or `Err(KeyDuplicate)` if a key of the same name has been made since.
+    pub fn trash_restore(&self, key: &str) -> Result<()> {
+        let mut statement = self.conn.prepare(TRASH_GET_SQL)?;
+        statement.bind((1, key))?;
+        if statement.next()? != State::Row {
+            return Err(TrashNonExist(key.to_owned()));
+        }
+        let value: String = statement.read::<String, _>("value")?;
+        let meta: Vec<(String, String)> =
+            serde_json::from_str(&statement.read::<String, _>("meta")?)
+                .map_err(|e| VaultMalformed(format!("metadata of {} in the trash: {}", key, e)))?;
+        drop(statement);
+        if self.key_get(key)?.is_some() {
+            return Err(KeyDuplicate(key.to_owned()));
+        }
+
+        self.insert(key, &value)?;
+        for (field, value) in &meta {
+            self.meta_insert(key, field, value)?;
+        }
+        let mut statement = self.conn.prepare(TRASH_DEL_SQL)?;
+        statement.bind((1, key))?;
+        while let Ok(State::Row) = statement.next() {}
+        self.audit("restore", Some(key))?;
+        self.log(key)
+    }
+
+    /// Permanently delete every key that was moved to the trash before `before`,
+    /// in seconds since the unix epoch, and its' delete from the operation log.
+    ///
+    /// A copy of the vault that has not synced since could then bring the key back.
+    ///
+    /// Returns how many keys were deleted.
+    pub fn trash_purge(&self, before: u64) -> Result<usize> {
+        let before: i64 = before.min(i64::MAX as u64) as i64;
+        let purged: Vec<String> = self
+            .trash_ls()?
+            .into_iter()
+            .filter(|(_, deleted)| (*deleted as i64) < before)
+            .map(|(key, _)| key)
+            .collect();
+        for key in &purged {
+            self.audit("purge", Some(key))?;
+        }
+
+        let mut statement = self.conn.prepare(OPLOG_PURGE_SQL)?;
+        statement.bind((1, before))?;
+        while let Ok(State::Row) = statement.next() {}
+
+        let mut statement = self.conn.prepare(TRASH_PURGE_SQL)?;
+        statement.bind((1, before))?;
+        while let Ok(State::Row) = statement.next() {}
+        Ok(purged.len())
+    }
 
+    fn remove(&self, key: &str) -> Result<()> {
         let mut statement = self.conn.prepare(PASSWORD_DEL_SQL)?;
         statement.bind((1, key))?;
         while let Ok(State::Row) = statement.next() {}
+
+        let mut statement = self.conn.prepare(META_DEL_ALL_SQL)?;
+        statement.bind((1, key))?;
+        while let Ok(State::Row) = statement.next() {}
         Ok(())
     }
 
+    /// Set metadata `field` (such as `username`) of password `key`, or remove it if `value` is `None`.
+    ///
+    /// Returns `Err(KeyNonExist)` if the key does not exist in this vault.
+    pub fn meta_set(&self, key: &str, field: &str, value: Option<&str>) -> Result<()> {
+        if self.key_get(key)?.is_none() {
+            return Err(KeyNonExist(key.to_owned()));
+        };
+
+        let mut statement = self.conn.prepare(META_DEL_SQL)?;
+        statement.bind((1, key))?;
+        statement.bind((2, field))?;
+        while let Ok(State::Row) = statement.next() {}
+
+        if let Some(value) = value {
+            self.meta_insert(key, field, value)?;
+        }
+        self.key_touch(key, now())?;
+        self.audit("edit", Some(key))?;
+        self.log(key)
+    }
+
+    fn meta_insert(&self, key: &str, field: &str, value: &str) -> Result<()> {
+        let mut statement = self.conn.prepare(META_SET_SQL)?;
+        statement.bind_iter::<_, (_, Value)>([
+            (":key", key.into()),
+            (":field", field.into()),
+            (":value", value.into()),
+        ])?;
+        while let Ok(State::Row) = statement.next() {}
+        Ok(())
+    }
+
+    /// Get metadata `field` of password `key`.
+    ///
+    /// Returns `None` if the key or field does not exist.
+    pub fn meta_get(&self, key: &str, field: &str) -> Result<Option<String>> {
+        let mut statement = self.conn.prepare(META_GET_SQL)?;
+        statement.bind((1, key))?;
+        statement.bind((2, field))?;
+        if let Ok(State::Row) = statement.next() {
+            return Ok(Some(statement.read::<String, _>("value")?));
+        }
+        Ok(None)
+    }
+
+    /// Get every metadata field of password `key` and its' value, sorted by field.
+    pub fn meta_ls(&self, key: &str) -> Result<Vec<(String, String)>> {
+        let mut statement = self.conn.prepare(META_LS_SQL)?;
+        statement.bind((1, key))?;
+        let mut fields: Vec<(String, String)> = Vec::new();
+        while let Ok(State::Row) = statement.next() {
+            fields.push((
+                statement.read::<String, _>("field")?,
+                statement.read::<String, _>("value")?,
+            ));
+        }
+        Ok(fields)
+    }
+
+    /// Get when password `key` or its' metadata last changed, in seconds since the unix epoch.
+    ///
+    /// Keys last changed before srpk kept track of this were changed at `0`.
+    /// Returns `None` if the key does not exist.
+    pub fn key_modified(&self, key: &str) -> Result<Option<u64>> {
+        let mut statement = self.conn.prepare(MODIFIED_GET_SQL)?;
+        statement.bind((1, key))?;
+        if let Ok(State::Row) = statement.next() {
+            return Ok(Some(statement.read::<i64, _>("modified")? as u64));
+        }
+        Ok(None)
+    }
+
+    /// Set when password `key` last changed to `modified`, such as when copying it from another vault.
+    pub fn key_touch(&self, key: &str, modified: u64) -> Result<()> {
+        let mut statement = self.conn.prepare(MODIFIED_SET_SQL)?;
+        statement.bind((1, modified as i64))?;
+        statement.bind((2, key))?;
+        while let Ok(State::Row) = statement.next() {}
+        Ok(())
+    }
+
+    /// Get the vault-wide value `name`, such as when it was last merged.
+    pub fn state_get(&self, name: &str) -> Result<Option<String>> {
+        let mut statement = self.conn.prepare(STATE_GET_SQL)?;
+        statement.bind((1, name))?;
+        if let Ok(State::Row) = statement.next() {
+            return Ok(Some(statement.read::<String, _>("value")?));
+        }
+        Ok(None)
+    }
+
+    /// Set the vault-wide value `name`.
+    pub fn state_set(&self, name: &str, value: &str) -> Result<()> {
+        let mut statement = self.conn.prepare(STATE_SET_SQL)?;
+        statement.bind_iter::<_, (_, Value)>([(":name", name.into()), (":value", value.into())])?;
+        while let Ok(State::Row) = statement.next() {}
+        Ok(())
+    }
+
+    /// Record every later change made through this vault in its' operation log as made by `device`.
+    pub fn set_device(&mut self, device: &str) {
+        self.device = Some(device.to_owned());
+    }
+
+    fn clock(&self) -> Result<u64> {
+        Ok(self
+            .state_get(CLOCK_STATE)?
+            .and_then(|c| c.parse().ok())
+            .unwrap_or(0))
+    }
+
+    /// Record that `device` last changed `key` at `clock`, deleting it if `deleted`.
+    ///
+    /// Only the latest operation on each key is kept, and never its' value,
+    /// which is read from the key itself when the log is shared.
+    fn op_write(&self, key: &str, clock: u64, device: &str, deleted: bool) -> Result<()> {
+        let mut statement = self.conn.prepare(OPLOG_SET_SQL)?;
+        statement.bind_iter::<_, (_, Value)>([
+            (":key", key.into()),
+            (":clock", (clock as i64).into()),
+            (":device", device.into()),
+            (":deleted", (deleted as i64).into()),
+        ])?;
+        while let Ok(State::Row) = statement.next() {}
+        Ok(())
+    }
+
+    /// Start logging changes for `srpk sync --dir`; until then, `log` does nothing.
+    pub fn oplog_enable(&self) -> Result<()> {
+        self.state_set(OPLOG_STATE, "on")
+    }
+
+    /// Record the current state of `key` in the operation log,
+    /// if a device is set and logging is enabled.
+    fn log(&self, key: &str) -> Result<()> {
+        let Some(device) = &self.device else {
+            return Ok(());
+        };
+        if self.state_get(OPLOG_STATE)?.is_none() {
+            return Ok(());
+        }
+        let clock: u64 = self.clock()? + 1;
+        let deleted: bool = self.key_get(key)?.is_none();
+        self.op_write(key, clock, device, deleted)?;
+        self.state_set(CLOCK_STATE, &clock.to_string())
+    }
+
+    /// Get the latest operation on every key the operation log knows of,
+    /// with the current password and metadata of each key that was not deleted.
+    pub fn oplog(&self) -> Result<Vec<Op>> {
+        let mut statement = self.conn.prepare(OPLOG_LS_SQL)?;
+        let mut ops: Vec<Op> = Vec::new();
+        while let Ok(State::Row) = statement.next() {
+            let key: String = statement.read::<String, _>("key")?;
+            let value: Option<String> = match statement.read::<i64, _>("deleted")? {
+                0 => statement.read::<Option<String>, _>("value")?,
+                _ => None,
+            };
+            let meta: Vec<(String, String)> = match value {
+                Some(_) => self.meta_ls(&key)?,
+                None => Vec::new(),
+            };
+            ops.push(Op {
+                clock: statement.read::<i64, _>("clock")? as u64,
+                device: statement.read::<String, _>("device")?,
+                key,
+                value,
+                meta,
+            });
+        }
+        Ok(ops)
+    }
+
+    /// Record keys that were never logged, such as those added before the log existed,
+    /// so that they can be synced, and keys in the trash as deleted.
+    /// Does nothing if no device is set.
+    ///
+    /// Seeded operations lose to any logged change, and seeded deletes win over seeded keys,
+    /// so a key deleted from one copy before it was logged stays deleted.
+    ///
+    /// Returns how many keys were recorded.
+    pub fn oplog_seed(&self) -> Result<usize> {
+        let Some(device) = &self.device else {
+            return Ok(0);
+        };
+        let unlogged = |sql: &str| -> Result<Vec<String>> {
+            let mut statement = self.conn.prepare(sql)?;
+            let mut keys: Vec<String> = Vec::new();
+            while let Ok(State::Row) = statement.next() {
+                keys.push(statement.read::<String, _>("key")?);
+            }
+            Ok(keys)
+        };
+        let (kept, deleted) = (
+            unlogged(OPLOG_UNLOGGED_SQL)?,
+            unlogged(OPLOG_UNLOGGED_TRASH_SQL)?,
+        );
+        for key in &kept {
+            self.op_write(key, SEED_CLOCK, device, false)?;
+        }
+        for key in &deleted {
+            self.op_write(key, SEED_DELETE_CLOCK, device, true)?;
+        }
+        Ok(kept.len() + deleted.len())
+    }
+
+    /// Apply the operations in `ops` that are newer than what this vault has for their key,
+    /// and move the clock past all of them.
+    ///
+    /// Returns how many operations were applied.
+    pub fn oplog_apply(&self, ops: &[Op]) -> Result<usize> {
+        let ours: Vec<Op> = self.oplog()?;
+        let newer: Vec<&Op> = oplog::newer(&ours, ops);
+        for op in &newer {
+            if op.value.is_none() && self.key_get(&op.key)?.is_some() {
+                self.trash(&op.key)?;
+            }
+            self.remove(&op.key)?;
+            if let Some(value) = &op.value {
+                self.insert(&op.key, value)?;
+                for (field, value) in &op.meta {
+                    self.meta_insert(&op.key, field, value)?;
+                }
+            }
+            self.op_write(&op.key, op.clock, &op.device, op.value.is_none())?;
+            self.audit("sync", Some(&op.key))?;
+        }
+        let clock: u64 = ops.iter().map(|op| op.clock).fold(self.clock()?, u64::max);
+        self.state_set(CLOCK_STATE, &clock.to_string())?;
+        Ok(newer.len())
+    }
+
+    /// Record `action` on `key`, or on the whole vault if `None`, in the audit log,
+    /// as done now by this host and user.
+    ///
+    /// Changes to the vault are recorded as they are made; reads are up to the caller.
+    /// The log can only be added to; it is kept only if the vault is closed with its' changes.
+    pub fn audit(&self, action: &str, key: Option<&str>) -> Result<()> {
+        let chain: [u8; 32] = self.audit_chain();
+        let (_, prev) = self.audit_head()?.unwrap_or((0, audit::GENESIS.to_owned()));
+        self.audit_add(&Event::new(action, key, now(), &prev, &chain))
+    }
+
+    fn audit_add(&self, event: &Event) -> Result<()> {
+        let mut statement = self.conn.prepare(AUDIT_ADD_SQL)?;
+        statement.bind_iter::<_, (_, Value)>([
+            (":time", (event.time as i64).into()),
+            (":host", event.host.as_str().into()),
+            (":user", event.user.as_str().into()),
+            (":action", event.action.as_str().into()),
+            (
+                ":key",
+                event.key.as_deref().map_or(Value::Null, Value::from),
+            ),
+            (":nonce", event.nonce.as_str().into()),
+            (":hash", event.hash.as_str().into()),
+        ])?;
+        while let Ok(State::Row) = statement.next() {}
+
+        let count: usize = self.audit_head()?.map_or(0, |(count, _)| count);
+        self.state_set(AUDIT_HEAD_STATE, &format!("{} {}", count + 1, event.hash))
+    }
+
+    /// Get the key the audit log is chained with, derived from the data key
+    /// so that only those who can open the vault can extend or rebuild the chain.
+    fn audit_chain(&self) -> [u8; 32] {
+        audit::chain_key(&self.key)
+    }
+
+    /// Chain the audit log again with the key derived from the current data key,
+    /// after checking it against `old`, the key it was chained with before a rekey.
+    ///
+    /// Events from the first one that did not check out are left as they were,
+    /// so a log that was changed still fails to verify afterwards.
+    fn audit_rechain(&self, old: &[u8; 32]) -> Result<()> {
+        let events: Vec<(i64, Event)> = self.audit_rows()?;
+        let head: Option<(usize, String)> = self.audit_head()?;
+        if events.is_empty() && head.is_none() {
+            return Ok(());
+        }
+        let list: Vec<Event> = events.iter().map(|(_, e)| e.clone()).collect();
+        let broken: Option<usize> = audit::verify(
+            &list,
+            old,
+            head.as_ref().map(|(count, hash)| (*count, hash.as_str())),
+        );
+
+        let chain: [u8; 32] = self.audit_chain();
+        let mut prev: String = audit::GENESIS.to_owned();
+        for (id, event) in events.iter().take(broken.unwrap_or(events.len())) {
+            prev = event.rechain(&prev, &chain).hash;
+            let mut statement = self.conn.prepare(AUDIT_REHASH_SQL)?;
+            statement.bind((1, prev.as_str()))?;
+            statement.bind((2, *id))?;
+            while let Ok(State::Row) = statement.next() {}
+        }
+        if broken.is_none() {
+            self.state_set(AUDIT_HEAD_STATE, &format!("{} {}", events.len(), prev))?;
+        }
+        Ok(())
+    }
+
+    /// Get how many events the audit log has and the hash of the last one, or `None` if it is empty.
+    fn audit_head(&self) -> Result<Option<(usize, String)>> {
+        let Some(head) = self.state_get(AUDIT_HEAD_STATE)? else {
+            return Ok(None);
+        };
+        let malformed = || VaultMalformed("audit head is malformed".to_string());
+        let (count, hash) = head.split_once(' ').ok_or_else(malformed)?;
+        Ok(Some((
+            count.parse().map_err(|_| malformed())?,
+            hash.to_owned(),
+        )))
+    }
+
+    /// Get every event in the audit log, oldest first.
+    pub fn audit_ls(&self) -> Result<Vec<Event>> {
+        Ok(self.audit_rows()?.into_iter().map(|(_, e)| e).collect())
+    }
+
+    fn audit_rows(&self) -> Result<Vec<(i64, Event)>> {
+        let mut statement = self.conn.prepare(AUDIT_LS_SQL)?;
+        let mut events: Vec<(i64, Event)> = Vec::new();
+        while let Ok(State::Row) = statement.next() {
+            events.push((
+                statement.read::<i64, _>("id")?,
+                Event {
+                    time: statement.read::<i64, _>("time")? as u64,
+                    host: statement.read::<String, _>("host")?,
+                    user: statement.read::<String, _>("user")?,
+                    action: statement.read::<String, _>("action")?,
+                    key: statement.read::<Option<String>, _>("key")?,
+                    nonce: statement.read::<String, _>("nonce")?,
+                    hash: statement.read::<String, _>("hash")?,
+                },
+            ));
+        }
+        Ok(events)
+    }
+
+    /// Check that no event in the audit log was changed or removed.
+    ///
+    /// Returns the index of the first event that was, as in `audit::verify`, or `None` if the log is intact.
+    pub fn audit_verify(&self) -> Result<Option<usize>> {
+        let events: Vec<Event> = self.audit_ls()?;
+        let head: Option<(usize, String)> = self.audit_head()?;
+        if events.is_empty() && head.is_none() {
+            return Ok(None);
+        }
+        Ok(audit::verify(
+            &events,
+            &self.audit_chain(),
+            head.as_ref().map(|(count, hash)| (*count, hash.as_str())),
+        ))
+    }
+
+    /// Check that `events`, the whole audit log of another copy of this vault
+    /// that shares its' data key, chain from one another with this vault's key.
+    ///
+    /// Returns the index of the first event that does not, or `None` if they all do.
+    pub fn audit_check(&self, events: &[Event]) -> Option<usize> {
+        let last: &str = events.last().map_or(audit::GENESIS, |e| e.hash.as_str());
+        audit::verify(events, &self.audit_chain(), Some((events.len(), last)))
+    }
+
+    /// Add the events of `theirs`, the audit log of another copy of the vault,
+    /// that this log does not have, ordered by when they happened and then by host,
+    /// after the events already here.
+    ///
+    /// Returns how many events were added.
+    pub fn audit_merge(&self, theirs: &[Event]) -> Result<usize> {
+        let ours: Vec<Event> = self.audit_ls()?;
+        let mut missing: Vec<&Event> = theirs
+            .iter()
+            .filter(|their| !ours.iter().any(|our| our.same(their)))
+            .collect();
+        missing.sort_by(|a, b| (a.time, &a.host).cmp(&(b.time, &b.host)));
+
+        let chain: [u8; 32] = self.audit_chain();
+        let mut prev: String = ours
+            .last()
+            .map_or(audit::GENESIS.to_owned(), |e| e.hash.clone());
+        for event in &missing {
+            let event: Event = event.rechain(&prev, &chain);
+            self.audit_add(&event)?;
+            prev = event.hash;
+        }
+        Ok(missing.len())
+    }
+
     /// Get a `Vec<String>` containing the names of each key in the vault.
     ///
     /// Returns an empty `Vec<String>` if no keys are in the vault.
@@ -214,6 +1031,207 @@ impl Vault {
         }
         Ok(keys)
     }
+
+    /// Run `f` on the vault as a single transaction,
+    /// so that either all of its' changes are kept or, if it fails, none of them.
+    ///
+    /// Example:
+    /// ```
+    /// let vault: Vault = Vault::open("./myvault.db", "mypassword")?;
+    /// vault.transaction(|v| {
+    ///     v.key_new("github", "password123!")?;
+    ///     v.key_new("gitlab", "password456!")
+    /// })?;
+    /// vault.close(true)?;
+    /// ```
+    pub fn transaction<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&Self) -> Result<T>,
+    {
+        self.conn.execute("BEGIN;")?;
+        match f(self) {
+            Ok(value) => {
+                self.conn.execute("COMMIT;")?;
+                Ok(value)
+            }
+            Err(e) => {
+                self.conn.execute("ROLLBACK;")?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Get the schema version of the vault database.
+    pub fn schema_version(&self) -> Result<usize> {
+        schema_version(&self.conn)
+    }
+
+    /// Run SQLite's integrity check on the vault database.
+    ///
+    /// Returns an empty `Vec<String>` if no problems were found.
+    pub fn integrity_check(&self) -> Result<Vec<String>> {
+        let mut statement = self.conn.prepare("PRAGMA integrity_check;")?;
+        let mut problems: Vec<String> = Vec::new();
+        while let Ok(State::Row) = statement.next() {
+            problems.push(statement.read::<String, _>(0)?);
+        }
+        if problems == ["ok"] {
+            problems.clear();
+        }
+        Ok(problems)
+    }
+
+    /// Get the names of each key that appears in the vault more than once.
+    pub fn key_duplicates(&self) -> Result<Vec<String>> {
+        let mut statement = self.conn.prepare(PASSWORD_DUPLICATES_SQL)?;
+        let mut keys: Vec<String> = Vec::new();
+        while let Ok(State::Row) = statement.next() {
+            keys.push(statement.read::<String, _>("key")?);
+        }
+        Ok(keys)
+    }
+}
+
+/// Shred the temporary database of a vault dropped without `Vault::close`, such as on an error,
+/// discarding its' changes so no plaintext is left behind.
+impl Drop for Vault {
+    fn drop(&mut self) {
+        let _ = self.release();
+    }
+}
+
+/// A way of unlocking the data key of a vault.
+#[derive(Clone, Copy)]
+pub enum Unlock<'a> {
+    /// The vault password.
+    Password(&'a str),
+    /// The X25519 secret of a recipient.
+    Identity(&'a [u8; 32]),
+    /// The raw data key.
+    Key(&'a [u8; 32]),
+}
+
+/// Whether the temporary database at `path` is locked by a srpk process that has it open.
+fn temp_locked(path: &Path) -> bool {
+    let Ok(file) = OpenOptions::new().write(true).open(path) else {
+        return false;
+    };
+    matches!(file.try_lock(), Err(TryLockError::WouldBlock))
+}
+
+fn temp_path(path: &str) -> PathBuf {
+    let mut path_str: String = path.to_owned();
+    path_str.push_str(".temp");
+    PathBuf::from(path_str)
+}
+
+/// Overwrite the file at `path` with zeroes before deleting it,
+/// so the plaintext is not left behind in the freed blocks.
+fn shred(path: &Path) -> Result<()> {
+    let len: u64 = metadata(path)?.len();
+    let mut file: File = OpenOptions::new().write(true).open(path)?;
+    let zeroes: [u8; 4096] = [0u8; 4096];
+    let mut written: u64 = 0;
+    while written < len {
+        let chunk: usize = (len - written).min(zeroes.len() as u64) as usize;
+        file.write_all(&zeroes[..chunk])?;
+        written += chunk as u64;
+    }
+    file.sync_all()?;
+    drop(file);
+    Ok(remove_file(path)?)
+}
+
+/// Decrypt the vault file `db_enc` `with` the given method.
+///
+/// Returns the data key, the slots, and the decrypted database.
+/// Legacy (password-only) vaults are given a new data key and a password slot.
+fn unlock(db_enc: &[u8], with: Unlock) -> Result<([u8; 32], Vec<Slot>, Vec<u8>)> {
+    let Some((header, aad, body)) = Header::parse(db_enc)? else {
+        let Unlock::Password(pass) = with else {
+            return Err(IdentityNoSlot);
+        };
+        let db_raw: CryptValue = aes256_decrypt(db_enc, pass)?;
+        let key: [u8; 32] = generate_key();
+        let slots: Vec<Slot> = vec![Slot::Password(aes256_encrypt(&key, pass, db_raw.cost)?)];
+        return Ok((key, slots, db_raw.value));
+    };
+
+    let key: [u8; 32] = match with {
+        Unlock::Password(pass) => header
+            .slots
+            .iter()
+            .find_map(|slot| match slot {
+                Slot::Password(payload) => aes256_decrypt(payload, pass).ok(),
+                _ => None,
+            })
+            .map(|k| unwrap_key(k.value))
+            .ok_or(AES256(aes_gcm_siv::Error))??,
+        Unlock::Identity(identity) => {
+            let public: [u8; 32] = x25519_public(identity);
+            let payload: &Vec<u8> = header
+                .slots
+                .iter()
+                .find_map(|slot| match slot {
+                    Slot::X25519 { recipient, payload } if recipient == &public => Some(payload),
+                    _ => None,
+                })
+                .ok_or(IdentityNoSlot)?;
+            unwrap_key(x25519_decrypt(payload, identity)?)?
+        }
+        Unlock::Key(key) => *key,
+    };
+
+    let db_raw: Vec<u8> = aes256_open_aad(body, &key, aad)?;
+    Ok((key, header.slots, db_raw))
+}
+
+/// Whether this process has written changes to a vault, by `Vault::close(true)` or `Vault::temp_merge`.
+pub fn written() -> bool {
+    WRITTEN.load(Ordering::Relaxed)
+}
+
+/// Get the current time in seconds since the unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn schema_version(conn: &Connection) -> Result<usize> {
+    let mut statement = conn.prepare("PRAGMA user_version;")?;
+    statement.next()?;
+    Ok(statement.read::<i64, _>(0)? as usize)
+}
+
+/// Bring the schema of `conn` up to `SCHEMA_VERSION`.
+///
+/// Returns `Err(SchemaNewer)` if the vault was made by a newer srpk.
+fn migrate(conn: &Connection) -> Result<()> {
+    let version: usize = schema_version(conn)?;
+    if version > SCHEMA_VERSION {
+        return Err(SchemaNewer(version));
+    }
+    for (i, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+        conn.execute(format!(
+            "BEGIN; {} PRAGMA user_version = {}; COMMIT;",
+            sql,
+            i + 1
+        ))?;
+    }
+    Ok(())
+}
+
+fn seal_vault(db_raw: &[u8], key: &[u8; 32], slots: Vec<Slot>) -> Result<Vec<u8>> {
+    let header: Vec<u8> = Header { slots }.to_bytes()?;
+    let body: Vec<u8> = aes256_seal_aad(db_raw, key, &header)?;
+    Ok([header, body].concat())
+}
+
+fn unwrap_key(bytes: Vec<u8>) -> Result<[u8; 32]> {
+    bytes
+        .try_into()
+        .map_err(|_| VaultMalformed("data key has wrong length".to_string()))
 }
 
 #[cfg(test)]
@@ -333,4 +1351,535 @@ mod tests {
 
         std::fs::remove_dir_all("vault_test_password_ls").unwrap();
     }
+
+    #[test]
+    fn test_recipient() {
+        std::fs::create_dir("vault_test_recipient").unwrap();
+        Vault::create("./vault_test_recipient/test.db", PASS, COST).unwrap();
+        let (secret, public) = crate::crypt::x25519_generate();
+        let (other_secret, _) = crate::crypt::x25519_generate();
+
+        let mut vault: Vault = Vault::open("./vault_test_recipient/test.db", PASS).unwrap();
+        vault.key_new(KEY1, PASS).unwrap();
+        vault.recipient_add(&public).unwrap();
+        assert!(vault.recipient_add(&public).is_err());
+        vault.close(true).unwrap();
+
+        let vault: Vault = Vault::open_identity("./vault_test_recipient/test.db", &secret).unwrap();
+        assert_eq!(vault.key_get(KEY1).unwrap().unwrap(), PASS);
+        vault.close(false).unwrap();
+        assert!(Vault::open_identity("./vault_test_recipient/test.db", &other_secret).is_err());
+
+        let mut vault: Vault = Vault::open("./vault_test_recipient/test.db", PASS).unwrap();
+        vault.recipient_del(&public).unwrap();
+        vault.close(true).unwrap();
+        assert!(Vault::open_identity("./vault_test_recipient/test.db", &secret).is_err());
+
+        std::fs::remove_dir_all("vault_test_recipient").unwrap();
+    }
+
+    #[test]
+    fn test_legacy_upgrade() {
+        std::fs::create_dir("vault_test_legacy_upgrade").unwrap();
+        let path: &str = "./vault_test_legacy_upgrade/test.db";
+        let connection = sqlite::open(path).unwrap();
+        connection
+            .execute("CREATE TABLE srpk (key TEXT, value TEXT);")
+            .unwrap();
+        drop(connection);
+        let db_raw: Vec<u8> = read(path).unwrap();
+        write(path, aes256_encrypt(&db_raw, PASS, COST).unwrap()).unwrap();
+
+        let vault: Vault = Vault::open(path, PASS).unwrap();
+        vault.key_new(KEY1, PASS).unwrap();
+        vault.close(true).unwrap();
+
+        assert!(Header::parse(&read(path).unwrap()).unwrap().is_some());
+        let vault: Vault = Vault::open(path, PASS).unwrap();
+        assert_eq!(vault.key_get(KEY1).unwrap().unwrap(), PASS);
+        vault.close(false).unwrap();
+
+        std::fs::remove_dir_all("vault_test_legacy_upgrade").unwrap();
+    }
+
+    #[test]
+    fn test_open_raw() {
+        std::fs::create_dir("vault_test_open_raw").unwrap();
+        let path: &str = "./vault_test_open_raw/test.db";
+        let connection = sqlite::open(path).unwrap();
+        connection
+            .execute("CREATE TABLE srpk (key TEXT, value TEXT); PRAGMA user_version = 1;")
+            .unwrap();
+        drop(connection);
+        let key: [u8; 32] = generate_key();
+        let slots: Vec<Slot> = vec![Slot::Password(aes256_encrypt(&key, PASS, COST).unwrap())];
+        write(path, seal_vault(&read(path).unwrap(), &key, slots).unwrap()).unwrap();
+
+        // an old schema is seen as it was written, and left alone
+        let vault: Vault = Vault::open_raw(path, Unlock::Password(PASS)).unwrap();
+        assert_eq!(vault.schema_version().unwrap(), 1);
+        assert!(vault.integrity_check().unwrap().is_empty());
+        vault.close(false).unwrap();
+        let vault: Vault = Vault::open(path, PASS).unwrap();
+        assert_eq!(vault.schema_version().unwrap(), SCHEMA_VERSION);
+        vault
+            .conn
+            .execute(format!("PRAGMA user_version = {};", SCHEMA_VERSION + 1))
+            .unwrap();
+        vault.close(true).unwrap();
+
+        // a newer schema can be inspected, though not opened for use
+        assert!(Vault::open(path, PASS).is_err());
+        let vault: Vault = Vault::open_raw(path, Unlock::Password(PASS)).unwrap();
+        assert_eq!(vault.schema_version().unwrap(), SCHEMA_VERSION + 1);
+        vault.close(false).unwrap();
+
+        std::fs::remove_dir_all("vault_test_open_raw").unwrap();
+    }
+
+    #[test]
+    fn test_header_authenticated() {
+        std::fs::create_dir("vault_test_header_authenticated").unwrap();
+        let path: &str = "./vault_test_header_authenticated/test.db";
+        Vault::create(path, PASS, COST).unwrap();
+        let bytes: Vec<u8> = read(path).unwrap();
+        let (header, aad, body) = Header::parse(&bytes).unwrap().unwrap();
+        let (key, _, db_raw) = unlock(&bytes, Unlock::Password(PASS)).unwrap();
+
+        // a slot added without the data key is caught, though the body is untouched
+        let mut slots: Vec<Slot> = header.slots.clone();
+        slots.push(Slot::Password(
+            aes256_encrypt(&generate_key(), "other", COST).unwrap(),
+        ));
+        let forged: Vec<u8> = [Header { slots }.to_bytes().unwrap(), body.to_vec()].concat();
+        write(path, forged).unwrap();
+        assert!(Vault::open(path, PASS).is_err());
+
+        // as is a body sealed without the header
+        write(
+            path,
+            [aad, &aes256_seal_aad(&db_raw, &key, &[]).unwrap()].concat(),
+        )
+        .unwrap();
+        assert!(Vault::open(path, PASS).is_err());
+
+        std::fs::remove_dir_all("vault_test_header_authenticated").unwrap();
+    }
+
+    #[test]
+    fn test_rekey() {
+        std::fs::create_dir("vault_test_rekey").unwrap();
+        Vault::create("./vault_test_rekey/test.db", PASS, COST).unwrap();
+        let (secret, public) = crate::crypt::x25519_generate();
+
+        let mut vault: Vault = Vault::open("./vault_test_rekey/test.db", PASS).unwrap();
+        let old_key: [u8; 32] = vault.data_key();
+        vault.key_new(KEY1, PASS).unwrap();
+        vault.recipient_add(&public).unwrap();
+        vault.rekey(KEY2, COST).unwrap();
+        vault.close(true).unwrap();
+
+        assert!(Vault::open("./vault_test_rekey/test.db", PASS).is_err());
+        assert!(Vault::open_key("./vault_test_rekey/test.db", &old_key).is_err());
+        let vault: Vault = Vault::open("./vault_test_rekey/test.db", KEY2).unwrap();
+        assert_eq!(vault.key_get(KEY1).unwrap().unwrap(), PASS);
+        let new_key: [u8; 32] = vault.data_key();
+        vault.close(false).unwrap();
+        Vault::open_key("./vault_test_rekey/test.db", &new_key)
+            .unwrap()
+            .close(false)
+            .unwrap();
+        Vault::open_identity("./vault_test_rekey/test.db", &secret)
+            .unwrap()
+            .close(false)
+            .unwrap();
+
+        std::fs::remove_dir_all("vault_test_rekey").unwrap();
+    }
+
+    #[test]
+    fn test_schema() {
+        std::fs::create_dir("vault_test_schema").unwrap();
+        Vault::create("./vault_test_schema/test.db", PASS, COST).unwrap();
+        let vault: Vault = Vault::open("./vault_test_schema/test.db", PASS).unwrap();
+        assert_eq!(vault.schema_version().unwrap(), SCHEMA_VERSION);
+        assert!(vault.integrity_check().unwrap().is_empty());
+        vault
+            .conn
+            .execute(format!("PRAGMA user_version = {};", SCHEMA_VERSION + 1))
+            .unwrap();
+        vault.close(true).unwrap();
+
+        assert!(Vault::open("./vault_test_schema/test.db", PASS).is_err());
+        assert!(!Path::new("./vault_test_schema/test.db.temp").exists());
+        std::fs::remove_dir_all("vault_test_schema").unwrap();
+    }
+
+    #[test]
+    fn test_meta() {
+        std::fs::create_dir("vault_test_meta").unwrap();
+        Vault::create("./vault_test_meta/test.db", PASS, COST).unwrap();
+        let vault: Vault = Vault::open("./vault_test_meta/test.db", PASS).unwrap();
+        assert!(vault.meta_set(KEY1, "username", Some("me")).is_err());
+        vault.key_new(KEY1, PASS).unwrap();
+        vault.meta_set(KEY1, "username", Some("me")).unwrap();
+        vault.meta_set(KEY1, "username", Some("you")).unwrap();
+        assert_eq!(vault.meta_get(KEY1, "username").unwrap().unwrap(), "you");
+        vault.meta_set(KEY1, "username", None).unwrap();
+        assert!(vault.meta_get(KEY1, "username").unwrap().is_none());
+        vault
+            .meta_set(KEY1, "url", Some("https://example.com"))
+            .unwrap();
+        vault.meta_set(KEY1, "notes", Some("hi")).unwrap();
+        assert_eq!(
+            vault.meta_ls(KEY1).unwrap(),
+            vec![
+                ("notes".to_owned(), "hi".to_owned()),
+                ("url".to_owned(), "https://example.com".to_owned())
+            ]
+        );
+
+        vault.meta_set(KEY1, "username", Some("me")).unwrap();
+        vault.key_del(KEY1).unwrap();
+        vault.key_new(KEY1, PASS).unwrap();
+        assert!(vault.meta_get(KEY1, "username").unwrap().is_none());
+        vault.close(false).unwrap();
+        std::fs::remove_dir_all("vault_test_meta").unwrap();
+    }
+
+    #[test]
+    fn test_modified() {
+        std::fs::create_dir("vault_test_modified").unwrap();
+        Vault::create("./vault_test_modified/test.db", PASS, COST).unwrap();
+        let vault: Vault = Vault::open("./vault_test_modified/test.db", PASS).unwrap();
+        assert!(vault.key_modified(KEY1).unwrap().is_none());
+        vault.key_new(KEY1, PASS).unwrap();
+        assert!(vault.key_modified(KEY1).unwrap().unwrap() >= now() - 1);
+        vault.key_touch(KEY1, 5).unwrap();
+        assert_eq!(vault.key_modified(KEY1).unwrap(), Some(5));
+        vault.meta_set(KEY1, "username", Some("me")).unwrap();
+        assert!(vault.key_modified(KEY1).unwrap().unwrap() > 5);
+
+        assert!(vault.state_get("merged").unwrap().is_none());
+        vault.state_set("merged", "1").unwrap();
+        vault.state_set("merged", "2").unwrap();
+        assert_eq!(vault.state_get("merged").unwrap().as_deref(), Some("2"));
+        vault.close(false).unwrap();
+        std::fs::remove_dir_all("vault_test_modified").unwrap();
+    }
+
+    #[test]
+    fn test_oplog() {
+        std::fs::create_dir("vault_test_oplog").unwrap();
+        Vault::create("./vault_test_oplog/a.db", PASS, COST).unwrap();
+        std::fs::copy("./vault_test_oplog/a.db", "./vault_test_oplog/b.db").unwrap();
+        let mut a: Vault = Vault::open("./vault_test_oplog/a.db", PASS).unwrap();
+        let mut b: Vault = Vault::open("./vault_test_oplog/b.db", PASS).unwrap();
+        a.key_new(KEY1, PASS).unwrap();
+        assert_eq!(a.oplog_seed().unwrap(), 0);
+        a.set_device("a");
+        b.set_device("b");
+        // nothing is logged until sync is enabled
+        a.key_new("before", PASS).unwrap();
+        a.key_del("before").unwrap();
+        assert!(a.oplog().unwrap().is_empty());
+        a.oplog_enable().unwrap();
+        b.oplog_enable().unwrap();
+        assert_eq!(a.oplog_seed().unwrap(), 2);
+        b.oplog_seed().unwrap();
+        a.oplog_apply(&b.oplog().unwrap()).unwrap();
+        b.oplog_apply(&a.oplog().unwrap()).unwrap();
+        assert!(b.key_get(KEY1).unwrap().is_some());
+
+        a.meta_set(KEY1, "username", Some("me")).unwrap();
+        b.key_new(KEY2, PASS).unwrap();
+        assert_eq!(b.oplog_apply(&a.oplog().unwrap()).unwrap(), 1);
+        assert_eq!(a.oplog_apply(&b.oplog().unwrap()).unwrap(), 1);
+        assert_eq!(b.meta_get(KEY1, "username").unwrap().unwrap(), "me");
+        assert_eq!(a.key_get(KEY2).unwrap().unwrap(), PASS);
+
+        // concurrent changes to one key end the same way on both copies
+        a.key_del(KEY2).unwrap();
+        b.meta_set(KEY2, "username", Some("you")).unwrap();
+        let (ops_a, ops_b) = (a.oplog().unwrap(), b.oplog().unwrap());
+        a.oplog_apply(&ops_b).unwrap();
+        b.oplog_apply(&ops_a).unwrap();
+        assert_eq!(a.oplog().unwrap(), b.oplog().unwrap());
+        let keys = |v: &Vault| -> Vec<String> {
+            let mut keys: Vec<String> = v.key_ls().unwrap();
+            keys.sort();
+            keys
+        };
+        assert_eq!(keys(&a), keys(&b));
+        assert_eq!(a.oplog_apply(&ops_b).unwrap(), 0);
+
+        // changes after a sync win over what was synced
+        b.key_del(KEY1).unwrap();
+        a.oplog_apply(&b.oplog().unwrap()).unwrap();
+        assert!(a.key_get(KEY1).unwrap().is_none());
+
+        // the log keeps no copies of secrets, and forgets deletes of keys purged from the trash
+        let stored = |v: &Vault| -> i64 {
+            let mut statement = v
+                .conn
+                .prepare("SELECT COUNT(*) FROM srpk_oplog WHERE value IS NOT NULL;")
+                .unwrap();
+            statement.next().unwrap();
+            statement.read::<i64, _>(0).unwrap()
+        };
+        assert_eq!(stored(&a) + stored(&b), 0);
+        assert!(a.oplog().unwrap().iter().any(|op| op.key == KEY1));
+        a.trash_purge(u64::MAX).unwrap();
+        assert!(!a.oplog().unwrap().iter().any(|op| op.key == KEY1));
+        a.close(false).unwrap();
+        b.close(false).unwrap();
+        std::fs::remove_dir_all("vault_test_oplog").unwrap();
+    }
+
+    #[test]
+    fn test_trash() {
+        std::fs::create_dir("vault_test_trash").unwrap();
+        Vault::create("./vault_test_trash/test.db", PASS, COST).unwrap();
+        let vault: Vault = Vault::open("./vault_test_trash/test.db", PASS).unwrap();
+        vault.key_new(KEY1, PASS).unwrap();
+        vault.meta_set(KEY1, "username", Some("me")).unwrap();
+        vault.key_new(KEY2, PASS).unwrap();
+        vault.key_del(KEY1).unwrap();
+        vault.key_del(KEY2).unwrap();
+
+        let keys: Vec<String> = vault
+            .trash_ls()
+            .unwrap()
+            .into_iter()
+            .map(|(k, _)| k)
+            .collect();
+        assert_eq!(keys, vec![KEY1, KEY2]);
+        vault.trash_restore(KEY1).unwrap();
+        assert_eq!(vault.key_get(KEY1).unwrap().unwrap(), PASS);
+        assert_eq!(vault.meta_get(KEY1, "username").unwrap().unwrap(), "me");
+        assert!(vault.trash_restore(KEY1).is_err());
+
+        vault.key_new(KEY2, "new").unwrap();
+        assert!(vault.trash_restore(KEY2).is_err());
+
+        // a copy whose metadata cannot be read is not restored without it
+        vault.key_del(KEY1).unwrap();
+        vault
+            .conn
+            .execute("UPDATE srpk_trash SET meta = 'x';")
+            .unwrap();
+        assert!(matches!(vault.trash_restore(KEY1), Err(VaultMalformed(_))));
+        assert_eq!(vault.trash_purge(now() - 60).unwrap(), 0);
+        assert_eq!(vault.trash_purge(u64::MAX).unwrap(), 2);
+        assert!(vault.trash_ls().unwrap().is_empty());
+        vault.close(false).unwrap();
+        std::fs::remove_dir_all("vault_test_trash").unwrap();
+    }
+
+    #[test]
+    fn test_audit() {
+        std::fs::create_dir("vault_test_audit").unwrap();
+        Vault::create("./vault_test_audit/test.db", PASS, COST).unwrap();
+        let mut vault: Vault = Vault::open("./vault_test_audit/test.db", PASS).unwrap();
+        vault.key_new(KEY1, PASS).unwrap();
+        vault.meta_set(KEY1, "username", Some("me")).unwrap();
+        vault.key_del(KEY1).unwrap();
+        vault.trash_restore(KEY1).unwrap();
+        vault.rekey("newpassword", COST).unwrap();
+        vault.close(true).unwrap();
+
+        // the chain is rebuilt with the new data key when rekeyed, and its' key is never stored
+        let vault: Vault = Vault::open("./vault_test_audit/test.db", "newpassword").unwrap();
+        assert_eq!(vault.state_get("audit_chain").unwrap(), None);
+        vault.audit("get", Some(KEY1)).unwrap();
+        let events: Vec<Event> = vault.audit_ls().unwrap();
+        let actions: Vec<&str> = events.iter().map(|e| e.action.as_str()).collect();
+        assert_eq!(actions, vec!["mk", "edit", "rm", "restore", "rekey", "get"]);
+        assert_eq!(events[4].key, None);
+        assert_eq!(vault.audit_verify().unwrap(), None);
+
+        // the log cannot be rewritten through the database
+        assert!(vault
+            .conn
+            .execute("UPDATE srpk_audit SET key = 'x';")
+            .is_err());
+        assert!(vault.conn.execute("DELETE FROM srpk_audit;").is_err());
+        assert_eq!(vault.audit_ls().unwrap(), events);
+
+        // and dropping the newest events around the triggers is caught by the head
+        vault
+            .conn
+            .execute(
+                "DROP TRIGGER srpk_audit_delete;
+                DELETE FROM srpk_audit WHERE id = (SELECT MAX(id) FROM srpk_audit);",
+            )
+            .unwrap();
+        assert_eq!(vault.audit_verify().unwrap(), Some(5));
+        vault.close(false).unwrap();
+        std::fs::remove_dir_all("vault_test_audit").unwrap();
+    }
+
+    #[test]
+    fn test_audit_merge() {
+        std::fs::create_dir("vault_test_audit_merge").unwrap();
+        Vault::create("./vault_test_audit_merge/a.db", PASS, COST).unwrap();
+        Vault::create("./vault_test_audit_merge/b.db", PASS, COST).unwrap();
+        let a: Vault = Vault::open("./vault_test_audit_merge/a.db", PASS).unwrap();
+        let b: Vault = Vault::open("./vault_test_audit_merge/b.db", PASS).unwrap();
+        a.key_new(KEY1, PASS).unwrap();
+        b.key_new(KEY2, PASS).unwrap();
+        b.key_del(KEY2).unwrap();
+        // the same read twice in a second is still two events
+        b.audit("get", Some(KEY1)).unwrap();
+        b.audit("get", Some(KEY1)).unwrap();
+
+        let theirs: Vec<Event> = b.audit_ls().unwrap();
+        assert_eq!(a.audit_merge(&theirs).unwrap(), 4);
+        assert_eq!(a.audit_merge(&theirs).unwrap(), 0);
+        let events: Vec<Event> = a.audit_ls().unwrap();
+        let actions: Vec<(&str, Option<&str>)> = events
+            .iter()
+            .map(|e| (e.action.as_str(), e.key.as_deref()))
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                ("mk", Some(KEY1)),
+                ("mk", Some(KEY2)),
+                ("rm", Some(KEY2)),
+                ("get", Some(KEY1)),
+                ("get", Some(KEY1))
+            ]
+        );
+        assert_eq!(a.audit_verify().unwrap(), None);
+
+        // a log is only checked against the key of the vault it came from
+        assert_eq!(b.audit_check(&theirs), None);
+        assert_eq!(a.audit_check(&theirs), Some(0));
+        let mut forged: Vec<Event> = theirs.clone();
+        forged[1].key = Some(KEY1.to_owned());
+        assert_eq!(b.audit_check(&forged), Some(1));
+        a.close(false).unwrap();
+        b.close(false).unwrap();
+        std::fs::remove_dir_all("vault_test_audit_merge").unwrap();
+    }
+
+    #[test]
+    fn test_oplog_seed_deleted() {
+        std::fs::create_dir("vault_test_oplog_seed_deleted").unwrap();
+        Vault::create("./vault_test_oplog_seed_deleted/a.db", PASS, COST).unwrap();
+        let a: Vault = Vault::open("./vault_test_oplog_seed_deleted/a.db", PASS).unwrap();
+        a.key_new(KEY1, PASS).unwrap();
+        a.close(true).unwrap();
+        std::fs::copy(
+            "./vault_test_oplog_seed_deleted/a.db",
+            "./vault_test_oplog_seed_deleted/b.db",
+        )
+        .unwrap();
+        let mut a: Vault = Vault::open("./vault_test_oplog_seed_deleted/a.db", PASS).unwrap();
+        let mut b: Vault = Vault::open("./vault_test_oplog_seed_deleted/b.db", PASS).unwrap();
+
+        // deleted from one copy before either logged anything, then both seed
+        b.key_del(KEY1).unwrap();
+        for (v, device) in [(&mut a, "z"), (&mut b, "a")] {
+            v.set_device(device);
+            v.oplog_enable().unwrap();
+            v.oplog_seed().unwrap();
+        }
+        a.oplog_apply(&b.oplog().unwrap()).unwrap();
+        b.oplog_apply(&a.oplog().unwrap()).unwrap();
+        assert!(a.key_get(KEY1).unwrap().is_none());
+        assert!(b.key_get(KEY1).unwrap().is_none());
+        a.close(false).unwrap();
+        b.close(false).unwrap();
+        std::fs::remove_dir_all("vault_test_oplog_seed_deleted").unwrap();
+    }
+
+    #[test]
+    fn test_transaction() {
+        std::fs::create_dir("vault_test_transaction").unwrap();
+        Vault::create("./vault_test_transaction/test.db", PASS, COST).unwrap();
+        let vault: Vault = Vault::open("./vault_test_transaction/test.db", PASS).unwrap();
+        vault
+            .transaction(|v| {
+                v.key_new(KEY1, PASS)?;
+                v.key_new(KEY1, PASS)
+            })
+            .unwrap_err();
+        assert!(vault.key_ls().unwrap().is_empty());
+        vault
+            .transaction(|v| {
+                v.key_new(KEY1, PASS)?;
+                v.key_new(KEY2, PASS)
+            })
+            .unwrap();
+        assert_eq!(vault.key_ls().unwrap(), vec![KEY1, KEY2]);
+        vault.close(false).unwrap();
+        std::fs::remove_dir_all("vault_test_transaction").unwrap();
+    }
+
+    #[test]
+    fn test_key_duplicates() {
+        std::fs::create_dir("vault_test_key_duplicates").unwrap();
+        Vault::create("./vault_test_key_duplicates/test.db", PASS, COST).unwrap();
+        let vault: Vault = Vault::open("./vault_test_key_duplicates/test.db", PASS).unwrap();
+        vault.key_new(KEY1, PASS).unwrap();
+        vault.key_new(KEY2, PASS).unwrap();
+        assert!(vault.key_duplicates().unwrap().is_empty());
+        vault
+            .conn
+            .execute(format!(
+                "INSERT INTO srpk (key, value) VALUES ('{}', 'other');",
+                KEY1
+            ))
+            .unwrap();
+        assert_eq!(vault.key_duplicates().unwrap(), vec![KEY1]);
+        vault.close(false).unwrap();
+        std::fs::remove_dir_all("vault_test_key_duplicates").unwrap();
+    }
+
+    #[test]
+    fn test_temp_stale() {
+        std::fs::create_dir("vault_test_temp_stale").unwrap();
+        let path: &str = "./vault_test_temp_stale/test.db";
+        Vault::create(path, PASS, COST).unwrap();
+
+        // a session still running is not stale
+        let mut vault: Vault = Vault::open(path, PASS).unwrap();
+        vault.key_new(KEY1, PASS).unwrap();
+        assert!(Vault::temp_stale(path).is_none());
+        assert!(matches!(Vault::open(path, PASS), Err(VaultInUse(_))));
+
+        // simulate a session that was killed before closing, which releases its' lock
+        drop(vault.temp.take());
+        std::mem::forget(vault);
+
+        assert!(Vault::temp_stale(path).is_some());
+        assert!(matches!(Vault::open(path, PASS), Err(TempStale(_))));
+        assert!(Vault::temp_merge(path, Unlock::Password(KEY2)).is_err());
+        Vault::temp_merge(path, Unlock::Password(PASS)).unwrap();
+        assert!(Vault::temp_stale(path).is_none());
+
+        let mut vault: Vault = Vault::open(path, PASS).unwrap();
+        assert_eq!(vault.key_get(KEY1).unwrap().unwrap(), PASS);
+        vault.key_new(KEY2, PASS).unwrap();
+        drop(vault.temp.take());
+        std::mem::forget(vault);
+
+        Vault::temp_shred(path).unwrap();
+        let vault: Vault = Vault::open(path, PASS).unwrap();
+        assert!(vault.key_get(KEY2).unwrap().is_none());
+        vault.close(false).unwrap();
+
+        // a vault dropped without closing, such as on an error, leaves nothing behind
+        let vault: Vault = Vault::open(path, PASS).unwrap();
+        vault.key_new(KEY2, PASS).unwrap();
+        drop(vault);
+        assert!(!Path::new(&temp_path(path)).exists());
+        let vault: Vault = Vault::open(path, PASS).unwrap();
+        assert!(vault.key_get(KEY2).unwrap().is_none());
+        vault.close(false).unwrap();
+
+        std::fs::remove_dir_all("vault_test_temp_stale").unwrap();
+    }
 }
@@ -0,0 +1,124 @@
+use crate::crypt::{MAX_COST, MIN_COST};
+use crate::errors::{Result, SrpkError::VaultMalformed};
+use crate::header::{Header, Slot};
+
+/// Length of an `aes256_seal` output holding nothing: nonce and tag.
+const SEAL_OVERHEAD: usize = 12 + 16;
+/// Length of an `aes256_encrypt` output holding a data key: cost, salt, seal, key.
+const PASSWORD_SLOT_LEN: usize = 1 + 16 + SEAL_OVERHEAD + 32;
+/// Length of an `x25519_encrypt` output holding a data key: ephemeral key, seal, key.
+const X25519_SLOT_LEN: usize = 32 + SEAL_OVERHEAD + 32;
+
+fn check_cost(cost: u8) -> Result<()> {
+    if !(MIN_COST..=MAX_COST).contains(&cost) {
+        return Err(VaultMalformed(format!(
+            "crypt cost {} is out of range",
+            cost
+        )));
+    }
+    Ok(())
+}
+
+/// Check the unencrypted parts of the vault file `bytes`, which needs no password.
+///
+/// Returns a short description of the vault format,
+/// or `Err(VaultMalformed)` describing the first problem found.
+pub fn check_header(bytes: &[u8]) -> Result<String> {
+    let Some((header, _, body)) = Header::parse(bytes)? else {
+        if bytes.is_empty() {
+            return Err(VaultMalformed("file is empty".to_string()));
+        }
+        check_cost(bytes[0])?;
+        if bytes.len() < 1 + 16 + SEAL_OVERHEAD {
+            return Err(VaultMalformed("file is truncated".to_string()));
+        }
+        return Ok(format!("legacy password-only format, cost {}", bytes[0]));
+    };
+
+    if header.slots.is_empty() {
+        return Err(VaultMalformed("no slots can unlock the vault".to_string()));
+    }
+    let mut passwords: Vec<u8> = Vec::new();
+    let mut recipients: usize = 0;
+    for slot in &header.slots {
+        match slot {
+            Slot::Password(payload) => {
+                if payload.len() != PASSWORD_SLOT_LEN {
+                    return Err(VaultMalformed("password slot has wrong length".to_string()));
+                }
+                check_cost(payload[0])?;
+                passwords.push(payload[0]);
+            }
+            Slot::X25519 { payload, .. } => {
+                if payload.len() != X25519_SLOT_LEN {
+                    return Err(VaultMalformed(
+                        "recipient slot has wrong length".to_string(),
+                    ));
+                }
+                recipients += 1;
+            }
+        }
+    }
+    if body.len() < SEAL_OVERHEAD {
+        return Err(VaultMalformed(
+            "encrypted database is truncated".to_string(),
+        ));
+    }
+
+    let costs: Vec<String> = passwords.iter().map(|c| c.to_string()).collect();
+    Ok(format!(
+        "{} password slot(s) (cost {}), {} recipient(s)",
+        passwords.len(),
+        costs.join(", "),
+        recipients
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypt::{
+        aes256_encrypt, aes256_seal_aad, generate_key, x25519_encrypt, x25519_generate,
+    };
+
+    const PASS: &str = "password";
+    const COST: u8 = 5u8;
+
+    fn vault_bytes() -> Vec<u8> {
+        let key: [u8; 32] = generate_key();
+        let (_, recipient) = x25519_generate();
+        let header: Header = Header {
+            slots: vec![
+                Slot::Password(aes256_encrypt(&key, PASS, COST).unwrap()),
+                Slot::X25519 {
+                    recipient,
+                    payload: x25519_encrypt(&key, &recipient).unwrap(),
+                },
+            ],
+        };
+        let header: Vec<u8> = header.to_bytes().unwrap();
+        let body: Vec<u8> = aes256_seal_aad(b"db", &key, &header).unwrap();
+        [header, body].concat()
+    }
+
+    #[test]
+    fn test_check_header() {
+        let summary: String = check_header(&vault_bytes()).unwrap();
+        assert_eq!(summary, "1 password slot(s) (cost 5), 1 recipient(s)");
+    }
+
+    #[test]
+    fn test_check_header_truncated() {
+        let bytes: Vec<u8> = vault_bytes();
+        assert!(check_header(&bytes[..bytes.len() - 20]).is_err());
+        assert!(check_header(&bytes[..40]).is_err());
+        assert!(check_header(&[]).is_err());
+    }
+
+    #[test]
+    fn test_check_header_legacy() {
+        let bytes: Vec<u8> = aes256_encrypt(b"db", PASS, COST).unwrap();
+        assert!(check_header(&bytes).unwrap().starts_with("legacy"));
+        assert!(check_header(&[40u8; 64]).is_err());
+    }
+}
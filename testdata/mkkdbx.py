@@ -0,0 +1,146 @@
+#!/usr/bin/env python3
+"""Write the KDBX 4 databases used by the tests in src/kdbx.rs.
+
+Needs the `cryptography` package. The password of every database is "password".
+"""
+import base64, gzip, hashlib, hmac, os, struct
+from cryptography.hazmat.primitives.ciphers import Cipher, algorithms, modes
+from cryptography.hazmat.primitives import padding
+from cryptography.hazmat.primitives.kdf.argon2 import Argon2id
+
+PASSWORD = b"password"
+CIPHER_AES256 = bytes.fromhex("31c1f2e6bf714350be5805216afc5aff")
+CIPHER_CHACHA20 = bytes.fromhex("d6038a2b8b6f4cb5a524339a31dbb59a")
+KDF_AES = bytes.fromhex("c9d9f39a628a4460bf740d08c18a4fea")
+KDF_ARGON2ID = bytes.fromhex("9e298b1956db4773b23dfc3ec6f0a1e6")
+
+
+def variant_dict(items):
+    out = struct.pack("<H", 0x0100)
+    for kind, key, value in items:
+        key = key.encode()
+        out += struct.pack("<BI", kind, len(key)) + key + struct.pack("<I", len(value)) + value
+    return out + b"\x00"
+
+
+def field(kind, data):
+    return struct.pack("<BI", kind, len(data)) + data
+
+
+def chacha20(key, iv, data):
+    return Cipher(algorithms.ChaCha20(key, b"\x00" * 4 + iv), None).encryptor().update(data)
+
+
+class InnerStream:
+    def __init__(self, key):
+        h = hashlib.sha512(key).digest()
+        self.enc = Cipher(algorithms.ChaCha20(h[:32], b"\x00" * 4 + h[32:44]), None).encryptor()
+
+    def protect(self, text):
+        return base64.b64encode(self.enc.update(text.encode())).decode()
+
+
+def xml(inner):
+    p = inner.protect
+    # protected values are encrypted in document order, history included
+    return f"""<?xml version="1.0" encoding="utf-8" standalone="yes"?>
+<KeePassFile>
+  <Meta><DatabaseName>test</DatabaseName></Meta>
+  <Root>
+    <Group>
+      <Name>Root</Name>
+      <Entry>
+        <String><Key>Title</Key><Value>github</Value></String>
+        <String><Key>UserName</Key><Value>me</Value></String>
+        <String><Key>Password</Key><Value Protected="True">{p("hunter2")}</Value></String>
+        <String><Key>URL</Key><Value>https://github.com</Value></String>
+        <String><Key>Notes</Key><Value>line one
+line two</Value></String>
+        <String><Key>Recovery</Key><Value Protected="True">{p("abcd-efgh")}</Value></String>
+        <Binary><Key>codes.txt</Key><Value Ref="0" /></Binary>
+        <History>
+          <Entry>
+            <String><Key>Title</Key><Value>github</Value></String>
+            <String><Key>Password</Key><Value Protected="True">{p("old password")}</Value></String>
+          </Entry>
+        </History>
+      </Entry>
+      <Group>
+        <Name>work</Name>
+        <Group>
+          <Name>prod</Name>
+          <Entry>
+            <String><Key>Title</Key><Value>db</Value></String>
+            <String><Key>UserName</Key><Value>admin</Value></String>
+            <String><Key>Password</Key><Value Protected="True">{p("s3cret <>")}</Value></String>
+          </Entry>
+        </Group>
+        <Entry>
+          <String><Key>Title</Key><Value>empty</Value></String>
+          <String><Key>Password</Key><Value Protected="True"></Value></String>
+        </Entry>
+      </Group>
+    </Group>
+  </Root>
+</KeePassFile>
+""".encode()
+
+
+def write(path, cipher, kdf):
+    master_seed = os.urandom(32)
+    iv = os.urandom(16 if cipher == CIPHER_AES256 else 12)
+    composite = hashlib.sha256(hashlib.sha256(PASSWORD).digest()).digest()
+    if kdf == KDF_ARGON2ID:
+        salt = os.urandom(32)
+        kdf_params = variant_dict([
+            (0x42, "$UUID", KDF_ARGON2ID), (0x42, "S", salt), (0x04, "P", struct.pack("<I", 1)),
+            (0x05, "M", struct.pack("<Q", 1024 * 1024)), (0x05, "I", struct.pack("<Q", 2)),
+            (0x04, "V", struct.pack("<I", 0x13)),
+        ])
+        transformed = Argon2id(salt=salt, length=32, iterations=2, lanes=1, memory_cost=1024).derive(composite)
+    else:
+        seed = os.urandom(32)
+        rounds = 1000
+        kdf_params = variant_dict([
+            (0x42, "$UUID", KDF_AES), (0x42, "S", seed), (0x05, "R", struct.pack("<Q", rounds)),
+        ])
+        enc = Cipher(algorithms.AES(seed), modes.ECB()).encryptor()
+        transformed = composite
+        for _ in range(rounds):
+            transformed = enc.update(transformed)
+        transformed = hashlib.sha256(transformed).digest()
+
+    header = struct.pack("<IIHH", 0x9AA2D903, 0xB54BFB67, 1, 4)
+    header += field(2, cipher) + field(3, struct.pack("<I", 1)) + field(4, master_seed)
+    header += field(7, iv) + field(11, kdf_params) + field(0, b"\r\n\r\n")
+
+    key = hashlib.sha256(master_seed + transformed).digest()
+    hmac_base = hashlib.sha512(master_seed + transformed + b"\x01").digest()
+    block_key = lambda i: hashlib.sha512(struct.pack("<Q", i) + hmac_base).digest()
+
+    inner_key = os.urandom(64)
+    inner = field(1, struct.pack("<I", 3)) + field(2, inner_key)
+    inner += field(3, b"\x01" + b"1234 5678\n") + field(0, b"")
+    payload = gzip.compress(inner + xml(InnerStream(inner_key)))
+
+    if cipher == CIPHER_AES256:
+        padder = padding.PKCS7(128).padder()
+        padded = padder.update(payload) + padder.finalize()
+        body = Cipher(algorithms.AES(key), modes.CBC(iv)).encryptor().update(padded)
+    else:
+        body = chacha20(key, iv, payload)
+
+    out = header + hashlib.sha256(header).digest()
+    out += hmac.new(block_key(0xFFFFFFFFFFFFFFFF), header, hashlib.sha256).digest()
+    blocks = [body[i:i + 4096] for i in range(0, len(body), 4096)] + [b""]
+    for i, block in enumerate(blocks):
+        size = struct.pack("<I", len(block))
+        mac = hmac.new(block_key(i), struct.pack("<Q", i) + size + block, hashlib.sha256).digest()
+        out += mac + size + block
+    with open(path, "wb") as f:
+        f.write(out)
+
+
+here = os.path.dirname(os.path.abspath(__file__))
+write(os.path.join(here, "argon2id-chacha20.kdbx"), CIPHER_CHACHA20, KDF_ARGON2ID)
+write(os.path.join(here, "aeskdf-aes256.kdbx"), CIPHER_AES256, KDF_AES)